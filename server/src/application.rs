@@ -1,18 +1,69 @@
 use base64::prelude::*;
+use chrono::Utc;
 use petgraph::graph::{DiGraph, NodeIndex};
 use regex::Regex;
 use std::collections::{HashMap, VecDeque};
 use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::runtime::Runtime;
 
+use crate::checkpoint::{self, BrowserState, Checkpoint};
+use crate::history::{History, RunMeta};
+use crate::misc::HttpRequest;
+use crate::pool::SessionPool;
+use crate::report::{CheckReport, NodeReport, RunReport};
 use crate::task::{
-    BaseTask, ConcurrentTack, DelayTack, EndTack, ExecutionResult, HeadTack, InitWebTack,
-    InputStringTack, OpenWebTack, PressButtonTack, SleepTack, SummitTack, Task, TimingTack,
-    Workspace,
+    AlertTack, AssertTack, BaseTask, ClearTack, CloseTabTack, ConcurrentTack, DelayTack,
+    DoubleClickTack, DownloadFileTack, EndTack, ExecutionResult, HeadTack, HoverTack,
+    HttpRequestTack, InitWebTack, InputStringTack, JsonExtractTack, LoadProfileTack, NewTabTack,
+    OpenWebTack, ParseHtmlTack, PressButtonTack, RegexExtractTack, RightClickTack, RunScriptTack,
+    SaveProfileTack, ScrapeTack, ScreenshotTack, ScrollByTack, ScrollIntoViewTack,
+    SelectOptionTack, SendKeysTack, SleepTack, SummitTack, SwitchFrameTack, SwitchTabTack, Task,
+    TimingTack, UploadFileTack, WebOption, Workspace,
 };
+use crate::task_helper;
+use crate::workflow;
+
+#[derive(Debug, Clone)]
+pub struct ExecuteOption {
+    pub artifact_root: PathBuf,
+    pub profile_root: PathBuf,
+    pub screenshot_on_failure: bool,
+    pub pool: Option<Arc<SessionPool>>,
+    pub cancel: Option<Arc<AtomicBool>>,
+    pub inputs: HashMap<String, String>,
+    pub history: Option<Arc<History>>,
+    pub meta: RunMeta,
+}
+
+impl Default for ExecuteOption {
+    fn default() -> Self {
+        ExecuteOption {
+            artifact_root: std::env::temp_dir().join("mashu").join("runs"),
+            profile_root: std::env::temp_dir().join("mashu").join("profiles"),
+            screenshot_on_failure: false,
+            pool: None,
+            cancel: None,
+            inputs: HashMap::new(),
+            history: None,
+            meta: RunMeta::default(),
+        }
+    }
+}
+
+// nodes that handle `${name}` themselves: http_request expands after splitting its
+// pairs, run_script passes variables as typed arguments next to JS template literals
+const SELF_EXPANDING: [&str; 2] = ["http_request", "run_script"];
 
 struct StepParser {
-    td: HashMap<String, Box<dyn Task>>, // td means task depositary
-    tf: DiGraph<String, String>,        // tf means task flowchart
+    td: HashMap<String, Box<dyn Task>>,    // td means task depositary
+    tf: DiGraph<String, String>,           // tf means task flowchart
+    ti: HashMap<String, (String, String)>, // ti means task information (type, name)
+    tp: HashMap<String, String>,           // tp means task parameters, kept when they use variables
 }
 
 impl StepParser {
@@ -20,109 +71,389 @@ impl StepParser {
         StepParser {
             td: HashMap::new(),
             tf: DiGraph::new(),
+            ti: HashMap::new(),
+            tp: HashMap::new(),
         }
     }
 
     fn arg_parse(&self, node_para: &str, key: &str) -> Option<String> {
-        let pattern = format!(r"{}:\s*'([^']*)'", key);
+        let pattern = format!(r"\b{}:\s*'([^']*)'", key);
         let re = Regex::new(&pattern).ok()?;
+        // quotes cannot appear literally in a node, mermaid's `#quot;` and `#39;` stand in for them
         re.captures(node_para)
             .and_then(|cap| cap.get(1))
             .map(|m| m.as_str().trim_matches('\'').to_string())
+            .map(|v| v.replace("#quot;", "\"").replace("#39;", "'"))
     }
 
-    fn register(
-        &mut self,
-        node_id: String,
-        node_type: String,
-        node_name: String,
-        node_para: String,
-    ) {
-        let task: Box<dyn Task> = match (node_type.as_str(), node_name.as_str()) {
-            ("control", "head") => Box::new(HeadTack::new()),
+    fn pairs_parse(
+        &self,
+        node_para: &str,
+        key: &str,
+        sep: char,
+        kv: char,
+    ) -> Vec<(String, String)> {
+        self.arg_parse(node_para, key)
+            .map(|v| {
+                v.split(sep)
+                    .filter_map(|p| p.split_once(kv))
+                    .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn web_option_parse(&self, node_para: &str) -> WebOption {
+        let flag = |key: &str| self.arg_parse(node_para, key).is_some_and(|v| v == "true");
+        WebOption {
+            browser: self
+                .arg_parse(node_para, "browser")
+                .unwrap_or("firefox".to_string()),
+            headless: flag("headless"),
+            window_size: self.arg_parse(node_para, "window_size").and_then(|v| {
+                let (w, h) = v.split_once('x')?;
+                Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+            }),
+            user_agent: self.arg_parse(node_para, "user_agent"),
+            proxy: self.arg_parse(node_para, "proxy"),
+            insecure: flag("insecure"),
+            download_dir: self.arg_parse(node_para, "download_dir"),
+            driver_path: self.arg_parse(node_para, "driver_path"),
+            unhandled_alert: self.arg_parse(node_para, "unhandled_alert"),
+            args: self
+                .arg_parse(node_para, "args")
+                .map(|v| v.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            prefs: self.pairs_parse(node_para, "prefs", ';', '='),
+        }
+    }
+
+    fn build(
+        &self,
+        node_type: &str,
+        node_name: &str,
+        node_para: &str,
+    ) -> Result<Box<dyn Task>, Box<dyn Error>> {
+        let task: Box<dyn Task> = match (node_type, node_name) {
+            ("control", "head") => {
+                let tz = self.arg_parse(node_para, "tz").unwrap_or_default();
+                Box::new(HeadTack::new(task_helper::tz_parse(&tz)?))
+            }
             ("control", "end") => Box::new(EndTack::new()),
             ("control", "sleep") => {
                 let ms = self
-                    .arg_parse(&node_para, "ms")
+                    .arg_parse(node_para, "ms")
                     .and_then(|v| v.parse::<u64>().ok());
                 Box::new(SleepTack::new(ms))
             }
             ("control", "timing") => {
-                let cron = self.arg_parse(&node_para, "cron");
-                Box::new(TimingTack::new(cron.as_deref()))
+                // a bad expression or timezone fails the whole script before anything runs
+                let cron = self
+                    .arg_parse(node_para, "cron")
+                    .unwrap_or("* * * * * * *".to_string());
+                let tz = self.arg_parse(node_para, "tz").unwrap_or_default();
+                Box::new(TimingTack::new(
+                    task_helper::cron_parse(&cron)?,
+                    task_helper::tz_parse(&tz)?,
+                ))
+            }
+            ("control", "assert") => {
+                let target = self.arg_parse(node_para, "target");
+                let component = self.arg_parse(node_para, "component");
+                let attribute = self.arg_parse(node_para, "attribute");
+                let variable = self.arg_parse(node_para, "variable");
+                let expect = self.arg_parse(node_para, "expect");
+                let mode = self.arg_parse(node_para, "match");
+                Box::new(AssertTack::new(
+                    target.as_deref(),
+                    component.as_deref(),
+                    attribute.as_deref(),
+                    variable.as_deref(),
+                    expect.as_deref(),
+                    mode.as_deref(),
+                ))
             }
             ("operate", "init_web") => {
-                let url = self.arg_parse(&node_para, "url");
-                Box::new(InitWebTack::new(url.as_deref()))
+                let url = self.arg_parse(node_para, "url");
+                let option = self.web_option_parse(node_para);
+                let session = self.arg_parse(node_para, "session");
+                let reset = self.arg_parse(node_para, "reset");
+                Box::new(InitWebTack::new(
+                    url.as_deref(),
+                    option,
+                    session.as_deref(),
+                    reset.as_deref(),
+                ))
             }
             ("operate", "open_web") => {
-                let url = self.arg_parse(&node_para, "url");
+                let url = self.arg_parse(node_para, "url");
                 Box::new(OpenWebTack::new(url.as_deref()))
             }
             ("operate", "input_string") => {
-                let component = self.arg_parse(&node_para, "component");
-                let input = self.arg_parse(&node_para, "input");
+                let component = self.arg_parse(node_para, "component");
+                let input = self.arg_parse(node_para, "input");
                 Box::new(InputStringTack::new(component.as_deref(), input.as_deref()))
             }
             ("operate", "press_button") => {
-                let component = self.arg_parse(&node_para, "component");
+                let component = self.arg_parse(node_para, "component");
                 Box::new(PressButtonTack::new(component.as_deref()))
             }
             ("operate", "summit") => {
-                let component = self.arg_parse(&node_para, "component");
+                let component = self.arg_parse(node_para, "component");
                 Box::new(SummitTack::new(component.as_deref()))
             }
+            ("operate", "select_option") => {
+                let component = self.arg_parse(node_para, "component");
+                let by = self.arg_parse(node_para, "by");
+                let option = self.arg_parse(node_para, "option");
+                Box::new(SelectOptionTack::new(
+                    component.as_deref(),
+                    by.as_deref(),
+                    option.as_deref(),
+                ))
+            }
+            ("operate", "hover") => {
+                let component = self.arg_parse(node_para, "component");
+                Box::new(HoverTack::new(component.as_deref()))
+            }
+            ("operate", "scroll_into_view") => {
+                let component = self.arg_parse(node_para, "component");
+                Box::new(ScrollIntoViewTack::new(component.as_deref()))
+            }
+            ("operate", "scroll_by") => {
+                let x = self
+                    .arg_parse(node_para, "x")
+                    .and_then(|v| v.parse::<i64>().ok());
+                let y = self
+                    .arg_parse(node_para, "y")
+                    .and_then(|v| v.parse::<i64>().ok());
+                Box::new(ScrollByTack::new(x, y))
+            }
+            ("operate", "clear") => {
+                let component = self.arg_parse(node_para, "component");
+                Box::new(ClearTack::new(component.as_deref()))
+            }
+            ("operate", "double_click") => {
+                let component = self.arg_parse(node_para, "component");
+                Box::new(DoubleClickTack::new(component.as_deref()))
+            }
+            ("operate", "right_click") => {
+                let component = self.arg_parse(node_para, "component");
+                Box::new(RightClickTack::new(component.as_deref()))
+            }
+            ("operate", "send_keys") => {
+                let component = self.arg_parse(node_para, "component");
+                let keys = self.arg_parse(node_para, "keys");
+                Box::new(SendKeysTack::new(component.as_deref(), keys.as_deref()))
+            }
+            ("operate", "switch_frame") => {
+                let component = self.arg_parse(node_para, "component");
+                let index = self
+                    .arg_parse(node_para, "index")
+                    .and_then(|v| v.parse::<u16>().ok());
+                let to = self.arg_parse(node_para, "to");
+                Box::new(SwitchFrameTack::new(
+                    component.as_deref(),
+                    index,
+                    to.as_deref(),
+                ))
+            }
+            ("operate", "new_tab") => {
+                let url = self.arg_parse(node_para, "url");
+                Box::new(NewTabTack::new(url.as_deref()))
+            }
+            ("operate", "switch_tab") => {
+                let index = self
+                    .arg_parse(node_para, "index")
+                    .and_then(|v| v.parse::<usize>().ok());
+                let title = self.arg_parse(node_para, "title");
+                let url = self.arg_parse(node_para, "url");
+                Box::new(SwitchTabTack::new(index, title.as_deref(), url.as_deref()))
+            }
+            ("operate", "close_tab") => Box::new(CloseTabTack::new()),
+            ("operate", "run_script") => {
+                let script = self.arg_parse(node_para, "script");
+                let is_async = self
+                    .arg_parse(node_para, "async")
+                    .is_some_and(|v| v == "true");
+                let args = self.arg_parse(node_para, "args");
+                let result = self.arg_parse(node_para, "result");
+                Box::new(RunScriptTack::new(
+                    script.as_deref(),
+                    is_async,
+                    args.as_deref(),
+                    result.as_deref(),
+                ))
+            }
+            ("operate", "upload_file") => {
+                let component = self.arg_parse(node_para, "component");
+                let path = self.arg_parse(node_para, "path");
+                Box::new(UploadFileTack::new(component.as_deref(), path.as_deref()))
+            }
+            ("operate", "download_file") => {
+                let component = self.arg_parse(node_para, "component");
+                let timeout = self
+                    .arg_parse(node_para, "timeout")
+                    .and_then(|v| v.parse::<u64>().ok());
+                let variable = self.arg_parse(node_para, "variable");
+                Box::new(DownloadFileTack::new(
+                    component.as_deref(),
+                    timeout,
+                    variable.as_deref(),
+                ))
+            }
+            ("operate", "alert") => {
+                let action = self.arg_parse(node_para, "action");
+                let input = self.arg_parse(node_para, "input");
+                let variable = self.arg_parse(node_para, "variable");
+                Box::new(AlertTack::new(
+                    action.as_deref(),
+                    input.as_deref(),
+                    variable.as_deref(),
+                ))
+            }
+            ("operate", "http_request") => {
+                let request = HttpRequest {
+                    method: self
+                        .arg_parse(node_para, "method")
+                        .unwrap_or("GET".to_string()),
+                    url: self.arg_parse(node_para, "url").unwrap_or_default(),
+                    headers: self.pairs_parse(node_para, "headers", ';', ':'),
+                    query: self.pairs_parse(node_para, "query", '&', '='),
+                    json: self.arg_parse(node_para, "json"),
+                    form: self.pairs_parse(node_para, "form", '&', '='),
+                    timeout_ms: self
+                        .arg_parse(node_para, "timeout")
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .unwrap_or(30000u64),
+                };
+                let expect = self.arg_parse(node_para, "expect");
+                let result = self.arg_parse(node_para, "result");
+                Box::new(HttpRequestTack::new(
+                    request,
+                    expect.as_deref(),
+                    result.as_deref(),
+                ))
+            }
+            ("operate", "parse_html") => {
+                let source = self.arg_parse(node_para, "source");
+                let url = self.arg_parse(node_para, "url");
+                let item = self.arg_parse(node_para, "item");
+                let fields = self.arg_parse(node_para, "fields").unwrap_or_default();
+                let result = self.arg_parse(node_para, "result");
+                let dataset = self.arg_parse(node_para, "dataset");
+                Box::new(ParseHtmlTack::new(
+                    source.as_deref(),
+                    url.as_deref(),
+                    item.as_deref(),
+                    task_helper::field_parse(&fields),
+                    result.as_deref(),
+                    dataset.as_deref(),
+                ))
+            }
+            ("operate", "scrape") => {
+                let item = self.arg_parse(node_para, "item");
+                let fields = self.arg_parse(node_para, "fields").unwrap_or_default();
+                let result = self.arg_parse(node_para, "result");
+                let dataset = self.arg_parse(node_para, "dataset");
+                Box::new(ScrapeTack::new(
+                    item.as_deref(),
+                    task_helper::field_parse(&fields),
+                    result.as_deref(),
+                    dataset.as_deref(),
+                ))
+            }
+            ("operate", "json_extract") => {
+                let source = self.arg_parse(node_para, "source");
+                let path = self.arg_parse(node_para, "path");
+                let target = self.arg_parse(node_para, "target");
+                let all = self
+                    .arg_parse(node_para, "all")
+                    .is_some_and(|v| v == "true");
+                Box::new(JsonExtractTack::new(
+                    source.as_deref(),
+                    path.as_deref(),
+                    target.as_deref(),
+                    all,
+                ))
+            }
+            ("operate", "regex_extract") => {
+                let source = self.arg_parse(node_para, "source");
+                let pattern = self.arg_parse(node_para, "pattern");
+                let target = self.arg_parse(node_para, "target");
+                let all = self
+                    .arg_parse(node_para, "all")
+                    .is_some_and(|v| v == "true");
+                Box::new(RegexExtractTack::new(
+                    source.as_deref(),
+                    pattern.as_deref(),
+                    target.as_deref(),
+                    all,
+                ))
+            }
+            ("operate", "screenshot") => {
+                let component = self.arg_parse(node_para, "component");
+                let name = self.arg_parse(node_para, "name");
+                Box::new(ScreenshotTack::new(component.as_deref(), name.as_deref()))
+            }
+            ("operate", "save_profile") => {
+                let profile = self.arg_parse(node_para, "profile");
+                Box::new(SaveProfileTack::new(profile.as_deref()))
+            }
+            ("operate", "load_profile") => {
+                let profile = self.arg_parse(node_para, "profile");
+                let url = self.arg_parse(node_para, "url");
+                Box::new(LoadProfileTack::new(profile.as_deref(), url.as_deref()))
+            }
             ("decorate", "delay") => {
                 let f_time = self
-                    .arg_parse(&node_para, "front_time")
+                    .arg_parse(node_para, "front_time")
                     .and_then(|v| v.parse::<u64>().ok());
                 let b_time = self
-                    .arg_parse(&node_para, "back_time")
+                    .arg_parse(node_para, "back_time")
                     .and_then(|v| v.parse::<u64>().ok());
                 Box::new(DelayTack::new(f_time, b_time))
             }
             ("decorate", "concurrent") => Box::new(ConcurrentTack::new()),
-            _ => Box::new(BaseTask::new(&*node_type, &*node_name)),
+            _ => Box::new(BaseTask::new(node_type, node_name)),
         };
+        Ok(task)
+    }
 
+    // the task is built once here to refuse broken parameters up front, nodes using
+    // `${name}` are built again from the expanded parameters right before they run
+    fn register(
+        &mut self,
+        node_id: String,
+        node_type: String,
+        node_name: String,
+        node_para: String,
+    ) -> Result<(), Box<dyn Error>> {
+        let task = self.build(&node_type, &node_name, &node_para)?;
+        if node_para.contains("${") && !SELF_EXPANDING.contains(&node_name.as_str()) {
+            self.tp.insert(node_id.clone(), node_para);
+        }
+        self.ti.insert(node_id.clone(), (node_type, node_name));
         self.td.insert(node_id, task);
+        Ok(())
     }
 
     fn parse_script(&mut self, raw: &str) -> Result<(), Box<dyn Error>> {
         let decode = BASE64_STANDARD.decode(raw)?;
         let decode = String::from_utf8(decode)?;
+        let flow = workflow::parse(&decode)?;
 
-        let node_pattern = Regex::new(
-            r#"(\w+)\["name:\s*([\w\s]+),\s*type:\s*(\w+)(?:,\s*para:\s*(\{[^"]*\}))?\s*"\]"#,
-        )?;
-        let edge_pattern = Regex::new(r#"(\w+)\s*-->\|\s*(\w+)\s*\|\s*(\w+)"#)?;
-
-        for cap in node_pattern.captures_iter(&decode) {
-            let node_id = cap.get(1).map_or("", |p| p.as_str()).to_string();
-            let node_name = cap.get(2).map_or("", |p| p.as_str()).to_string();
-            let node_type = cap.get(3).map_or("", |p| p.as_str()).to_string();
-            let node_para = cap.get(4).map_or("", |p| p.as_str()).to_string();
-
-            self.tf.add_node(node_id.clone());
-            self.register(node_id, node_type, node_name, node_para);
+        for node in flow.nodes {
+            self.tf.add_node(node.id.clone());
+            self.register(node.id, node.node_type, node.name, node.para)?;
         }
 
-        for cap in edge_pattern.captures_iter(&decode) {
-            let source = cap
-                .get(1)
-                .map_or("".to_string(), |p| p.as_str().to_string());
-            let decide = cap
-                .get(2)
-                .map_or("".to_string(), |p| p.as_str().to_string());
-            let target = cap
-                .get(3)
-                .map_or("".to_string(), |p| p.as_str().to_string());
-
+        for edge in flow.edges {
             if let (Some(src_idx), Some(dst_idx)) = (
-                self.tf.node_indices().find(|i| self.tf[*i] == source),
-                self.tf.node_indices().find(|i| self.tf[*i] == target),
+                self.tf.node_indices().find(|i| self.tf[*i] == edge.source),
+                self.tf.node_indices().find(|i| self.tf[*i] == edge.target),
             ) {
-                self.tf.add_edge(src_idx, dst_idx, decide);
+                self.tf.add_edge(src_idx, dst_idx, edge.label);
             }
         }
         Ok(())
@@ -131,17 +462,54 @@ impl StepParser {
 struct Executor {
     ws: Workspace,
     parser: StepParser,
+    script: String,
+    browser: Option<BrowserState>,
     queue: VecDeque<NodeIndex>,
+    option: ExecuteOption,
+    report: RunReport,
 }
 
 impl Executor {
-    fn new(id: &str, script: &str) -> Self {
+    fn new(id: &str, script: &str, option: &ExecuteOption) -> Result<Self, Box<dyn Error>> {
         let mut parser = StepParser::new();
-        parser.parse_script(script).expect("parser fail");
-        Executor {
+        parser.parse_script(script)?;
+
+        let run_id = format!("{}-{}", id, Utc::now().format("%Y%m%d%H%M%S%3f"));
+        let mut ws = Workspace::new(&format!("{}-{}", id, "ws01"));
+        ws.artifact_dir = option.artifact_root.join(&run_id);
+        ws.profile_dir = option.profile_root.clone();
+        ws.pool = option.pool.clone();
+        ws.cancel = option.cancel.clone();
+        // inputs are plain variables, visible to every node through `${name}`
+        for (key, value) in &option.inputs {
+            ws.set_variable(key, value);
+        }
+        Ok(Executor {
             parser,
+            script: script.to_string(),
+            browser: None,
             queue: VecDeque::new(),
-            ws: Workspace::new(&format!("{}-{}", id, "ws01")),
+            report: RunReport::new(&run_id, &ws.artifact_dir),
+            option: option.clone(),
+            ws,
+        })
+    }
+
+    fn _capture_failure(&mut self, node_id: &str) {
+        let driver = match self.ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let prefix = format!("fail-{}", node_id);
+        match rt.block_on(task_helper::capture_page(
+            driver,
+            &self.ws.artifact_dir,
+            &prefix,
+        )) {
+            Ok(paths) => paths.iter().for_each(|p| self.ws.add_artifact(p)),
+            Err(e) => self.ws.log(&format!("capture failure fail: {}", e)),
         }
     }
 
@@ -186,7 +554,7 @@ impl Executor {
         self._navigate_next_task(node, target);
     }
 
-    fn execute_flow(&mut self) -> Result<(), Box<dyn Error>> {
+    fn _seed_queue(&mut self) {
         for i in self.parser.tf.node_indices() {
             if self
                 .parser
@@ -199,22 +567,260 @@ impl Executor {
                 break;
             }
         }
+    }
 
+    fn _save_checkpoint(&mut self, node_id: &str, node_type: &str) {
+        // only operate nodes touch the page, after the others the last capture still holds,
+        // as it does while an alert is open and the page can't be read
+        self.browser = match self.ws.get_web_driver() {
+            Some(_) if node_type == "operate" => {
+                let captured = checkpoint::capture_browser(&self.ws);
+                if let Some(state) = &captured {
+                    self.ws.note_origin(&state.current_url);
+                }
+                captured.or(self.browser.take())
+            }
+            Some(_) => self.browser.take(),
+            None => None,
+        };
+        let mut point = Checkpoint {
+            run_id: self.report.run_id.clone(),
+            script: self.script.clone(),
+            screenshot_on_failure: self.option.screenshot_on_failure,
+            meta: self.option.meta.clone(),
+            current: node_id.to_string(),
+            queue: self
+                .queue
+                .iter()
+                .map(|i| self.parser.tf[*i].clone())
+                .collect(),
+            variables: self.ws.variables.clone(),
+            datasets: self.ws.datasets.clone(),
+            timezone: self.ws.timezone.map(|tz| tz.name().to_string()),
+            browser: self.browser.clone(),
+            report: std::mem::take(&mut self.report),
+            nodes: 0,
+            saved_at: String::new(),
+        };
+        if let Err(e) = point.save(&self.ws.artifact_dir) {
+            println!("save checkpoint fail: {}", e);
+        }
+        self.report = point.report;
+    }
+
+    fn execute_flow(&mut self) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.ws.artifact_dir)?;
+
+        let mut cancelled = false;
         while let Some(curr) = self.queue.pop_front() {
+            // cancellation is checked between nodes, within a node only the long waits
+            // (sleep, timing, download_file) give up early, other commands run to the end
+            if self.ws.is_cancelled() {
+                self.ws.log("run cancelled");
+                cancelled = true;
+                break;
+            }
+
             let node_id = self.parser.tf[curr].clone();
             println!("run node id: {}", node_id);
 
-            if let Some(task) = self.parser.td.get(&node_id) {
-                let result = task.execute(&mut self.ws);
-                self._result_route(curr, result);
+            let task = match self.parser.td.get(&node_id) {
+                Some(task) => task,
+                None => continue,
+            };
+
+            let log_mark = self.ws.execution_log.len();
+            let artifact_mark = self.ws.artifacts.len();
+            let check_mark = self.ws.checks.len();
+            let started_at = Utc::now().to_rfc3339();
+            let expanded = self.parser.tp.get(&node_id).map(|para| {
+                let (node_type, node_name) = &self.parser.ti[&node_id];
+                self.parser
+                    .build(node_type, node_name, &self.ws.expand_param(para))
+            });
+            let timer = Instant::now();
+            let result = match &expanded {
+                Some(Ok(task)) => task.execute(&mut self.ws),
+                Some(Err(e)) => {
+                    self.ws.log(&format!("invalid parameters: {}", e));
+                    ExecutionResult::Failure
+                }
+                None => task.execute(&mut self.ws),
+            };
+            let elapsed_ms = timer.elapsed().as_millis() as u64;
+
+            let (node_type, node_name) = self.parser.ti[&node_id].clone();
+            if matches!(result, ExecutionResult::Failure)
+                && node_type == "operate"
+                && self.option.screenshot_on_failure
+            {
+                self._capture_failure(&node_id);
+            }
+
+            let node = NodeReport {
+                node_id: node_id.clone(),
+                node_type: node_type.clone(),
+                node_name,
+                result: format!("{:?}", result),
+                started_at,
+                elapsed_ms,
+                logs: self
+                    .ws
+                    .execution_log
+                    .get(log_mark..)
+                    .unwrap_or_default()
+                    .to_vec(),
+                artifacts: self.ws.artifacts[artifact_mark..].to_vec(),
+            };
+            if let Err(e) = Checkpoint::record_node(&self.ws.artifact_dir, &node) {
+                println!("record node report fail: {}", e);
+            }
+            self.report.push(node);
+            for check in &self.ws.checks[check_mark..] {
+                self.report.push_check(CheckReport {
+                    node_id: node_id.clone(),
+                    ..check.clone()
+                });
+            }
+            self._result_route(curr, result);
+            self._save_checkpoint(&node_id, &node_type);
+        }
+
+        // hand a borrowed session back even when the flow never reached an end node
+        self.ws.release_web_driver();
+        self.report.finish();
+        // a cancelled run keeps its checkpoint so it can be resumed later
+        if cancelled {
+            self.report.status = "cancelled".to_string();
+        } else {
+            Checkpoint::remove(&self.ws.artifact_dir);
+        }
+        self.report.save()?;
+        if let Some(history) = &self.option.history {
+            if let Err(e) = history.record(&self.option.meta, &self.report) {
+                println!("record run history fail: {}", e);
             }
         }
         Ok(())
     }
 }
 
-pub fn app(raw: &str) -> Result<(), Box<dyn Error>> {
-    let mut executor = Executor::new("e1", &raw);
+// parses the script without running it, so a broken workflow is refused up front
+pub fn check(raw: &str) -> Result<(), Box<dyn Error>> {
+    StepParser::new().parse_script(raw)
+}
+
+pub fn app(raw: &str, option: &ExecuteOption) -> Result<RunReport, Box<dyn Error>> {
+    let mut executor = Executor::new("e1", raw, option)?;
+    executor._seed_queue();
+    executor.execute_flow()?;
+    Ok(executor.report)
+}
+
+/// Continues a run from the checkpoint in its artifact directory, with a fresh
+/// browser carrying over the saved cookies and storage.
+pub fn resume(artifact_dir: &Path, option: &ExecuteOption) -> Result<RunReport, Box<dyn Error>> {
+    let point = Checkpoint::load(artifact_dir)?;
+    point.rewind_nodes(artifact_dir)?;
+    let option = ExecuteOption {
+        screenshot_on_failure: point.screenshot_on_failure,
+        meta: point.meta.clone(),
+        ..option.clone()
+    };
+    let mut executor = Executor::new("e1", &point.script, &option)?;
+    executor.ws.artifact_dir = artifact_dir.to_path_buf();
+    executor.ws.variables = point.variables;
+    executor.ws.datasets = point.datasets;
+    executor.ws.timezone = task_helper::tz_parse(point.timezone.as_deref().unwrap_or(""))?;
+    executor.report = point.report;
+    for node_id in &point.queue {
+        let index = executor
+            .parser
+            .tf
+            .node_indices()
+            .find(|i| executor.parser.tf[*i] == *node_id)
+            .ok_or(format!("node {} not found in checkpoint script", node_id))?;
+        executor.queue.push_back(index);
+    }
+    if let Some(browser) = &point.browser {
+        checkpoint::restore_browser(&mut executor.ws, browser)?;
+    }
+    executor.browser = point.browser;
+    executor.ws.log(&format!(
+        "resume {} after node {}",
+        point.run_id, point.current
+    ));
     executor.execute_flow()?;
-    Ok(())
+    Ok(executor.report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::Checkpoint;
+
+    const FLOW: &str = r#"
+        flowchart TD
+        a["name: head, type: control"]
+        b["name: regex_extract, type: operate, para: {source: 'page', pattern: 'id=([0-9]+)', target: 'id'}"]
+        c["name: end, type: control"]
+        a -->|success| b
+        b -->|success| c
+    "#;
+
+    fn node(node_id: &str, node_type: &str, node_name: &str) -> NodeReport {
+        NodeReport {
+            node_id: node_id.to_string(),
+            node_type: node_type.to_string(),
+            node_name: node_name.to_string(),
+            result: "Success".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resume_continues_from_a_saved_checkpoint() {
+        let root = std::env::temp_dir().join(format!("mashu-resume-{}", std::process::id()));
+        let dir = root.join("e1-saved");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut report = RunReport::new("e1-saved", &dir);
+        report.push(node("a", "control", "head"));
+        Checkpoint::record_node(&dir, &report.nodes[0]).unwrap();
+        let mut point = Checkpoint {
+            run_id: "e1-saved".to_string(),
+            script: BASE64_STANDARD.encode(FLOW),
+            screenshot_on_failure: false,
+            meta: RunMeta::default(),
+            current: "a".to_string(),
+            queue: vec!["b".to_string()],
+            variables: HashMap::from([("page".to_string(), "id=42".to_string())]),
+            datasets: HashMap::new(),
+            timezone: None,
+            browser: None,
+            report,
+            nodes: 0,
+            saved_at: String::new(),
+        };
+        point.save(&dir).unwrap();
+        // b was recorded, but the run stopped before its checkpoint was written
+        Checkpoint::record_node(&dir, &node("b", "operate", "regex_extract")).unwrap();
+
+        let loaded = Checkpoint::load(&dir).unwrap();
+        assert_eq!(loaded.script, point.script);
+        assert_eq!(loaded.report.nodes.len(), 1);
+
+        let option = ExecuteOption {
+            artifact_root: root.clone(),
+            ..Default::default()
+        };
+        let report = resume(&dir, &option).unwrap();
+        let ids: Vec<&str> = report.nodes.iter().map(|n| n.node_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(report.status, "success");
+        assert_eq!(report.run_id, "e1-saved");
+        assert!(!Checkpoint::path(&dir).exists());
+        assert!(dir.join("report.json").exists());
+    }
 }
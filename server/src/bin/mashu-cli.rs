@@ -0,0 +1,146 @@
+use base64::prelude::*;
+use mashu_lib::application::{self, ExecuteOption};
+use mashu_lib::workflow::{self, Flow};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: mashu-cli <workflow file> [options]
+
+options:
+    --input key=value     set a run input, visible to nodes as ${key} (repeatable)
+    --out <dir>           directory for run artifacts and report.json
+    --profiles <dir>      directory for saved browser profiles
+    --screenshot          capture the page when an operate node fails
+    --check               only parse the workflow, do not run it
+    -h, --help            print this help
+
+the workflow file is mermaid source, a JSON flow ({\"nodes\": [...], \"edges\": [...]})
+or a workflow exported from the library ({\"source\": \"...\"})";
+
+struct Args {
+    file: PathBuf,
+    inputs: HashMap<String, String>,
+    out: Option<PathBuf>,
+    profiles: Option<PathBuf>,
+    screenshot: bool,
+    check: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut file = None;
+    let mut inputs = HashMap::new();
+    let (mut out, mut profiles) = (None, None);
+    let (mut screenshot, mut check) = (false, false);
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--input" => {
+                let pair = value("--input")?;
+                let (key, value) = pair
+                    .split_once('=')
+                    .ok_or(format!("input must be key=value: {}", pair))?;
+                inputs.insert(key.trim().to_string(), value.to_string());
+            }
+            "--out" => out = Some(PathBuf::from(value("--out")?)),
+            "--profiles" => profiles = Some(PathBuf::from(value("--profiles")?)),
+            "--screenshot" => screenshot = true,
+            "--check" => check = true,
+            flag if flag.starts_with('-') => return Err(format!("unknown option: {}", flag)),
+            _ if file.is_some() => return Err(format!("unexpected argument: {}", arg)),
+            _ => file = Some(PathBuf::from(arg)),
+        }
+    }
+
+    Ok(Some(Args {
+        file: file.ok_or("missing workflow file")?,
+        inputs,
+        out,
+        profiles,
+        screenshot,
+        check,
+    }))
+}
+
+// anything that is not JSON is taken as mermaid source as is
+fn load_source(path: &Path) -> Result<String, Box<dyn Error>> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    if !raw.trim_start().starts_with('{') {
+        return Ok(raw);
+    }
+    let value: Value = serde_json::from_str(&raw)?;
+    if let Some(source) = value.get("source").and_then(Value::as_str) {
+        return Ok(source.to_string());
+    }
+    let flow: Flow = serde_json::from_value(value)?;
+    Ok(workflow::render(&flow))
+}
+
+fn run(args: Args) -> Result<bool, Box<dyn Error>> {
+    let script = BASE64_STANDARD.encode(load_source(&args.file)?);
+    application::check(&script)?;
+    if args.check {
+        println!("workflow ok: {}", args.file.display());
+        return Ok(true);
+    }
+
+    let mut option = ExecuteOption {
+        screenshot_on_failure: args.screenshot,
+        inputs: args.inputs,
+        ..Default::default()
+    };
+    if let Some(out) = args.out {
+        option.artifact_root = out;
+    }
+    if let Some(profiles) = args.profiles {
+        option.profile_root = profiles;
+    }
+    option.meta.trigger = "cli".to_string();
+
+    let report = application::app(&script, &option)?;
+    for node in &report.nodes {
+        println!(
+            "{:<8} {} ({}, {}) {}ms",
+            node.result, node.node_id, node.node_type, node.node_name, node.elapsed_ms
+        );
+    }
+    for check in report.failed_checks() {
+        println!("check failed on {}: {}", check.node_id, check.message);
+    }
+    println!("run {}: {}", report.run_id, report.status);
+    println!(
+        "report: {}",
+        Path::new(&report.artifact_dir)
+            .join("report.json")
+            .display()
+    );
+    Ok(report.status == "success")
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
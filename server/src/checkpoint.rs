@@ -0,0 +1,181 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::runtime::Runtime;
+
+use crate::history::RunMeta;
+use crate::profile::{self, Profile};
+use crate::report::{NodeReport, RunReport};
+use crate::task::{WebOption, Workspace};
+use crate::task_helper::{self, Row};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserState {
+    pub remote_url: Option<String>,
+    pub option: WebOption,
+    pub current_url: String,
+    /// The frames entered on that page, outermost first.
+    #[serde(default)]
+    pub frames: Vec<String>,
+    pub profile: Profile,
+}
+
+/// Everything needed to continue a run after the node named in `current`.
+/// The script and the node reports don't change once written, so they are kept
+/// next to it in their own files instead of being rewritten after every node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub run_id: String,
+    #[serde(skip)]
+    pub script: String,
+    pub screenshot_on_failure: bool,
+    pub meta: RunMeta,
+    pub current: String,
+    pub queue: Vec<String>,
+    pub variables: HashMap<String, String>,
+    pub datasets: HashMap<String, Vec<Row>>,
+    pub timezone: Option<String>,
+    pub browser: Option<BrowserState>,
+    /// The report without its nodes, `nodes` says how many of the recorded ones it has.
+    pub report: RunReport,
+    pub nodes: usize,
+    pub saved_at: String,
+}
+
+impl Checkpoint {
+    pub fn path(artifact_dir: &Path) -> PathBuf {
+        artifact_dir.join("checkpoint.json")
+    }
+
+    fn script_path(artifact_dir: &Path) -> PathBuf {
+        artifact_dir.join("checkpoint.script")
+    }
+
+    fn nodes_path(artifact_dir: &Path) -> PathBuf {
+        artifact_dir.join("checkpoint.nodes.jsonl")
+    }
+
+    pub fn load(artifact_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let raw = fs::read_to_string(Checkpoint::path(artifact_dir))
+            .map_err(|_| format!("no checkpoint in {}", artifact_dir.display()))?;
+        let mut point: Checkpoint = serde_json::from_str(&raw)?;
+        point.script = fs::read_to_string(Checkpoint::script_path(artifact_dir))?;
+        // lines past the count belong to a node that ran after the last save, it runs again
+        let nodes = fs::read_to_string(Checkpoint::nodes_path(artifact_dir))?;
+        point.report.nodes = nodes
+            .lines()
+            .take(point.nodes)
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        Ok(point)
+    }
+
+    // written to a temporary file first, so a crash mid-write keeps the previous checkpoint
+    pub fn save(&mut self, artifact_dir: &Path) -> Result<(), Box<dyn Error>> {
+        self.saved_at = Utc::now().to_rfc3339();
+        let script = Checkpoint::script_path(artifact_dir);
+        if !script.exists() {
+            fs::write(script, &self.script)?;
+        }
+        let nodes = std::mem::take(&mut self.report.nodes);
+        self.nodes = nodes.len();
+        let raw = serde_json::to_string(self);
+        self.report.nodes = nodes;
+
+        let path = Checkpoint::path(artifact_dir);
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, raw?)?;
+        fs::rename(temp, path)?;
+        Ok(())
+    }
+
+    /// Appends one finished node to the reports a later checkpoint refers to.
+    pub fn record_node(artifact_dir: &Path, node: &NodeReport) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Checkpoint::nodes_path(artifact_dir))?;
+        writeln!(file, "{}", serde_json::to_string(node)?)?;
+        Ok(())
+    }
+
+    /// Rewrites the recorded nodes to match a loaded checkpoint before its run continues.
+    pub fn rewind_nodes(&self, artifact_dir: &Path) -> Result<(), Box<dyn Error>> {
+        let mut raw = String::new();
+        for node in &self.report.nodes {
+            raw.push_str(&serde_json::to_string(node)?);
+            raw.push('\n');
+        }
+        fs::write(Checkpoint::nodes_path(artifact_dir), raw)?;
+        Ok(())
+    }
+
+    pub fn remove(artifact_dir: &Path) {
+        let _ = fs::remove_file(Checkpoint::path(artifact_dir));
+        let _ = fs::remove_file(Checkpoint::script_path(artifact_dir));
+        let _ = fs::remove_file(Checkpoint::nodes_path(artifact_dir));
+    }
+}
+
+fn is_web(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Captures the page and, on an http(s) page, the cookies and storage of its origin.
+/// `None` without a browser or while an alert is open, as any other command would
+/// make the browser dismiss the alert before the next node can handle it.
+pub fn capture_browser(ws: &Workspace) -> Option<BrowserState> {
+    let driver = ws.get_web_driver()?;
+    let rt = Runtime::new().expect("create runtime fail");
+    let (current_url, saved) = rt.block_on(async {
+        if driver.get_alert_text().await.is_ok() {
+            return None;
+        }
+        let url = driver
+            .current_url()
+            .await
+            .map_or(String::new(), |url| url.to_string());
+        let mut saved = Profile::default();
+        if is_web(&url) {
+            if let Ok(state) = profile::export_origin(driver).await {
+                saved.merge(state);
+            }
+        }
+        Some((url, saved))
+    })?;
+
+    Some(BrowserState {
+        remote_url: ws.remote_url.clone(),
+        option: ws.web_option.clone(),
+        current_url,
+        frames: ws.frames.clone(),
+        profile: saved,
+    })
+}
+
+/// Starts a fresh browser with the saved options and puts the session state back,
+/// down to the frame the run was in.
+pub fn restore_browser(ws: &mut Workspace, state: &BrowserState) -> Result<(), String> {
+    ws.start_web_driver(state.remote_url.as_deref(), state.option.clone())?;
+    let driver = ws.get_web_driver().ok_or("browser not started")?;
+    let rt = Runtime::new().expect("create runtime fail");
+    let window = rt
+        .block_on(async {
+            profile::import_profile(driver, &state.profile).await?;
+            if is_web(&state.current_url) {
+                driver.goto(&state.current_url).await?;
+                task_helper::enter_frames(driver, &state.frames).await?;
+            }
+            driver.window().await
+        })
+        .map_err(|e| format!("restore browser fail: {}", e))?;
+    ws.set_window(Some(window));
+    if is_web(&state.current_url) {
+        ws.frames = state.frames.clone();
+    }
+    Ok(())
+}
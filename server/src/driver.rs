@@ -0,0 +1,123 @@
+use std::env;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug)]
+pub struct DriverProcess {
+    child: Child,
+    pub url: String,
+}
+
+fn binary_name(browser: &str) -> &'static str {
+    match browser {
+        "chrome" => "chromedriver",
+        "edge" => "msedgedriver",
+        _ => "geckodriver",
+    }
+}
+
+// each driver has its own override, e.g. MASHU_CHROMEDRIVER or MASHU_GECKODRIVER
+fn path_var(browser: &str) -> String {
+    format!("MASHU_{}", binary_name(browser).to_uppercase())
+}
+
+fn locate(browser: &str, path: Option<&str>) -> Result<PathBuf, String> {
+    // an explicit path wins over the browser's MASHU_* variable, which wins over PATH lookup
+    if let Some(path) = path.map(String::from).or(env::var(path_var(browser)).ok()) {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(format!("driver not found: {}", path.display()));
+        }
+        return Ok(path);
+    }
+
+    let name = format!("{}{}", binary_name(browser), env::consts::EXE_SUFFIX);
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dir| dir.join(&name))
+        .find(|candidate| candidate.is_file())
+        .ok_or(format!("{} not found in PATH", name))
+}
+
+fn free_port() -> Result<u16, String> {
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    Ok(port)
+}
+
+impl DriverProcess {
+    pub fn spawn(browser: &str, path: Option<&str>) -> Result<Self, String> {
+        let binary = locate(browser, path)?;
+        let port = free_port()?;
+
+        let mut command = Command::new(&binary);
+        match browser {
+            "chrome" | "edge" => command.arg(format!("--port={}", port)),
+            _ => command.arg("--port").arg(port.to_string()),
+        };
+        let child = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("spawn {} fail: {}", binary.display(), e))?;
+
+        let mut process = DriverProcess {
+            child,
+            url: format!("http://127.0.0.1:{}", port),
+        };
+        process.wait_ready()?;
+        Ok(process)
+    }
+
+    fn is_ready(&self) -> bool {
+        reqwest::blocking::get(format!("{}/status", self.url))
+            .and_then(|resp| resp.json::<serde_json::Value>())
+            .is_ok_and(|body| body["value"]["ready"] == true)
+    }
+
+    fn wait_ready(&mut self) -> Result<(), String> {
+        let deadline = Instant::now() + READY_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return Err(format!("driver exited early: {}", status));
+            }
+            if self.is_ready() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        Err(format!("driver not ready at {}", self.url))
+    }
+
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// dropping the workspace — normal end, early return or cancellation — stops the driver
+impl Drop for DriverProcess {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_browser_has_its_own_path_variable() {
+        assert_eq!(path_var("chrome"), "MASHU_CHROMEDRIVER");
+        assert_eq!(path_var("edge"), "MASHU_MSEDGEDRIVER");
+        assert_eq!(path_var("firefox"), "MASHU_GECKODRIVER");
+        assert_eq!(path_var(""), "MASHU_GECKODRIVER");
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{Manager, State};
+
+use crate::application;
+use crate::history::{History, Retention, RunDetail, RunFilter, RunMeta, RunRecord};
+use crate::library::{
+    Library, Revision, RevisionSummary, Workflow, WorkflowDraft, WorkflowSummary,
+};
+use crate::misc;
+use crate::pool::{PoolConfig, SessionPool, SessionSummary};
+use crate::scheduler::{self, CatchUp, CronPreview, Job, Overlap, Scheduler, Trigger};
+use crate::workflow::Change;
+
+#[tauri::command]
+fn greet(name: &str) -> String {
+    return format!("Hello, {}! You've been greeted from Rust!", name);
+}
+
+#[tauri::command]
+async fn get_web_page(
+    url: &str,
+    option: Option<misc::FetchOption>,
+) -> Result<misc::FetchResponse, misc::FetchError> {
+    println!("browse web pages: {}", url);
+    misc::fetch(url, &option.unwrap_or_default()).await
+}
+
+fn execute_script(
+    app: &tauri::AppHandle,
+    pool: &Arc<SessionPool>,
+    script: &str,
+    screenshot_on_failure: Option<bool>,
+    inputs: Option<HashMap<String, String>>,
+    meta: RunMeta,
+) -> Result<String, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let option = application::ExecuteOption {
+        artifact_root: data_dir.join("runs"),
+        profile_root: data_dir.join("profiles"),
+        screenshot_on_failure: screenshot_on_failure.unwrap_or(false),
+        pool: Some(pool.clone()),
+        cancel: None,
+        inputs: inputs.unwrap_or_default(),
+        history: Some(app.state::<Arc<History>>().inner().clone()),
+        meta,
+    };
+
+    match application::app(script, &option) {
+        Ok(report) => Ok(format!(
+            "run {}, report: {}",
+            report.status, report.artifact_dir
+        )),
+        Err(e) => Err(format!("error: {}", e)),
+    }
+}
+
+#[tauri::command]
+fn run_workflow(
+    app: tauri::AppHandle,
+    pool: State<'_, Arc<SessionPool>>,
+    script: &str,
+    screenshot_on_failure: Option<bool>,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    execute_script(
+        &app,
+        pool.inner(),
+        script,
+        screenshot_on_failure,
+        inputs,
+        RunMeta::default(),
+    )
+}
+
+#[tauri::command]
+fn run_saved_workflow(
+    app: tauri::AppHandle,
+    pool: State<'_, Arc<SessionPool>>,
+    library: State<'_, Library>,
+    id: &str,
+    revision: Option<u32>,
+    screenshot_on_failure: Option<bool>,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let (script, revision) = match revision {
+        Some(revision) => (library.revision(id, revision)?.script(), revision),
+        None => {
+            let workflow = library.get(id)?;
+            (workflow.script(), workflow.revision)
+        }
+    };
+    let meta = RunMeta {
+        workflow_id: Some(id.to_string()),
+        revision: Some(revision),
+        ..Default::default()
+    };
+    execute_script(
+        &app,
+        pool.inner(),
+        &script,
+        screenshot_on_failure,
+        inputs,
+        meta,
+    )
+}
+
+#[tauri::command]
+fn resume_run(
+    app: tauri::AppHandle,
+    pool: State<'_, Arc<SessionPool>>,
+    run_id: &str,
+) -> Result<String, String> {
+    if !misc::is_safe_name(run_id) {
+        return Err(format!("invalid run id: {}", run_id));
+    }
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let option = application::ExecuteOption {
+        artifact_root: data_dir.join("runs"),
+        profile_root: data_dir.join("profiles"),
+        pool: Some(pool.inner().clone()),
+        history: Some(app.state::<Arc<History>>().inner().clone()),
+        ..Default::default()
+    };
+
+    match application::resume(&option.artifact_root.join(run_id), &option) {
+        Ok(report) => Ok(format!(
+            "run {}, report: {}",
+            report.status, report.artifact_dir
+        )),
+        Err(e) => Err(format!("error: {}", e)),
+    }
+}
+
+#[tauri::command]
+fn list_runs(
+    history: State<'_, Arc<History>>,
+    filter: RunFilter,
+) -> Result<Vec<RunRecord>, String> {
+    history.query(&filter)
+}
+
+#[tauri::command]
+fn get_run(history: State<'_, Arc<History>>, run_id: &str) -> Result<RunDetail, String> {
+    history.get(run_id)
+}
+
+#[tauri::command]
+fn purge_runs(history: State<'_, Arc<History>>, retention: Retention) -> Result<usize, String> {
+    history.purge(&retention)
+}
+
+#[tauri::command]
+fn create_workflow(library: State<'_, Library>, draft: WorkflowDraft) -> Result<Workflow, String> {
+    library.create(draft)
+}
+
+#[tauri::command]
+fn list_workflows(library: State<'_, Library>) -> Vec<WorkflowSummary> {
+    library.list()
+}
+
+#[tauri::command]
+fn get_workflow(library: State<'_, Library>, id: &str) -> Result<Workflow, String> {
+    library.get(id)
+}
+
+#[tauri::command]
+fn update_workflow(
+    library: State<'_, Library>,
+    id: &str,
+    draft: WorkflowDraft,
+) -> Result<Workflow, String> {
+    library.update(id, draft)
+}
+
+#[tauri::command]
+fn duplicate_workflow(
+    library: State<'_, Library>,
+    id: &str,
+    name: Option<&str>,
+) -> Result<Workflow, String> {
+    library.duplicate(id, name)
+}
+
+#[tauri::command]
+fn delete_workflow(library: State<'_, Library>, id: &str) -> Result<(), String> {
+    library.delete(id)
+}
+
+#[tauri::command]
+fn configure_session_pool(
+    pool: State<'_, Arc<SessionPool>>,
+    max_size: usize,
+    idle_timeout_secs: u64,
+) -> PoolConfig {
+    pool.configure(PoolConfig {
+        max_size,
+        idle_timeout_secs,
+    });
+    pool.config()
+}
+
+#[tauri::command]
+fn list_pooled_sessions(pool: State<'_, Arc<SessionPool>>) -> Vec<SessionSummary> {
+    pool.list()
+}
+
+#[tauri::command]
+fn clear_session_pool(pool: State<'_, Arc<SessionPool>>) {
+    pool.clear();
+}
+
+#[tauri::command]
+fn add_schedule(
+    scheduler: State<'_, Arc<Scheduler>>,
+    name: &str,
+    script: &str,
+    trigger: Trigger,
+    catch_up: Option<CatchUp>,
+    overlap: Option<Overlap>,
+) -> Result<Job, String> {
+    scheduler.add(
+        name,
+        script,
+        trigger,
+        catch_up.unwrap_or_default(),
+        overlap.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+fn list_schedules(scheduler: State<'_, Arc<Scheduler>>) -> Vec<Job> {
+    scheduler.list()
+}
+
+#[tauri::command]
+fn pause_schedule(scheduler: State<'_, Arc<Scheduler>>, id: &str) -> Result<Job, String> {
+    scheduler.pause(id)
+}
+
+#[tauri::command]
+fn resume_schedule(scheduler: State<'_, Arc<Scheduler>>, id: &str) -> Result<Job, String> {
+    scheduler.resume(id)
+}
+
+#[tauri::command]
+fn delete_schedule(scheduler: State<'_, Arc<Scheduler>>, id: &str) -> Result<(), String> {
+    scheduler.remove(id)
+}
+
+#[tauri::command]
+fn run_schedule_now(scheduler: State<'_, Arc<Scheduler>>, id: &str) -> Result<(), String> {
+    scheduler.run_now(id)
+}
+
+#[tauri::command]
+fn list_workflow_revisions(
+    library: State<'_, Library>,
+    id: &str,
+) -> Result<Vec<RevisionSummary>, String> {
+    library.revisions(id)
+}
+
+#[tauri::command]
+fn get_workflow_revision(
+    library: State<'_, Library>,
+    id: &str,
+    revision: u32,
+) -> Result<Revision, String> {
+    library.revision(id, revision)
+}
+
+#[tauri::command]
+fn diff_workflow_revisions(
+    library: State<'_, Library>,
+    id: &str,
+    from: u32,
+    to: u32,
+) -> Result<Vec<Change>, String> {
+    library.diff(id, from, to)
+}
+
+#[tauri::command]
+fn restore_workflow_revision(
+    library: State<'_, Library>,
+    id: &str,
+    revision: u32,
+) -> Result<Workflow, String> {
+    library.restore(id, revision)
+}
+
+#[tauri::command]
+fn preview_cron(expr: &str, tz: Option<&str>, count: Option<usize>) -> Result<CronPreview, String> {
+    scheduler::preview_cron(expr, tz.unwrap_or(""), count.unwrap_or(5).min(100))
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let pool = Arc::new(SessionPool::new(PoolConfig::default()));
+    pool.start_reaper();
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_shell::init())
+        .manage(pool)
+        .setup(|app| {
+            // scheduled runs share the session pool and data dir with manual runs
+            let data_dir = app.path().app_data_dir()?;
+            let history = Arc::new(History::open(&data_dir.join("history.sqlite"))?);
+            app.manage(history.clone());
+            let option = application::ExecuteOption {
+                artifact_root: data_dir.join("runs"),
+                profile_root: data_dir.join("profiles"),
+                pool: Some(app.state::<Arc<SessionPool>>().inner().clone()),
+                history: Some(history),
+                ..Default::default()
+            };
+            let scheduler = Arc::new(Scheduler::load(data_dir.join("schedules.json"), option));
+            scheduler.start();
+            app.manage(scheduler);
+            app.manage(Library::new(data_dir.join("workflows")));
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            get_web_page,
+            run_workflow,
+            configure_session_pool,
+            list_pooled_sessions,
+            clear_session_pool,
+            add_schedule,
+            list_schedules,
+            pause_schedule,
+            resume_schedule,
+            delete_schedule,
+            run_schedule_now,
+            preview_cron,
+            run_saved_workflow,
+            create_workflow,
+            list_workflows,
+            get_workflow,
+            update_workflow,
+            duplicate_workflow,
+            delete_workflow,
+            list_workflow_revisions,
+            get_workflow_revision,
+            diff_workflow_revisions,
+            restore_workflow_revision,
+            resume_run,
+            list_runs,
+            get_run,
+            purge_runs
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<Arc<SessionPool>>().clear();
+            }
+        });
+}
@@ -0,0 +1,500 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::report::{CheckReport, NodeReport, RunReport};
+
+const SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS runs (
+        run_id TEXT PRIMARY KEY,
+        workflow_id TEXT,
+        revision INTEGER,
+        trigger TEXT NOT NULL,
+        status TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        started_ms INTEGER NOT NULL,
+        ended_at TEXT NOT NULL,
+        artifact_dir TEXT NOT NULL,
+        checks TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS nodes (
+        run_id TEXT NOT NULL REFERENCES runs(run_id) ON DELETE CASCADE,
+        seq INTEGER NOT NULL,
+        node_id TEXT NOT NULL,
+        node_type TEXT NOT NULL,
+        node_name TEXT NOT NULL,
+        result TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        elapsed_ms INTEGER NOT NULL,
+        logs TEXT NOT NULL,
+        artifacts TEXT NOT NULL,
+        PRIMARY KEY (run_id, seq)
+    );
+    CREATE INDEX IF NOT EXISTS runs_workflow ON runs(workflow_id, started_ms);
+    CREATE INDEX IF NOT EXISTS runs_started ON runs(started_ms);
+"#;
+
+/// Where a run came from, recorded next to its report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMeta {
+    pub workflow_id: Option<String>,
+    pub revision: Option<u32>,
+    pub trigger: String,
+}
+
+impl Default for RunMeta {
+    fn default() -> Self {
+        RunMeta {
+            workflow_id: None,
+            revision: None,
+            trigger: "manual".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub workflow_id: Option<String>,
+    pub revision: Option<u32>,
+    pub trigger: String,
+    pub status: String,
+    pub started_at: String,
+    pub ended_at: String,
+    pub artifact_dir: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDetail {
+    pub record: RunRecord,
+    pub report: RunReport,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RunFilter {
+    pub workflow_id: Option<String>,
+    pub status: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    pub max_age_days: Option<u32>,
+    pub keep_last: Option<u32>,
+    pub remove_artifacts: bool,
+}
+
+fn millis(raw: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|t| t.timestamp_millis())
+        .map_err(|e| format!("invalid time {}: {}", raw, e))
+}
+
+fn db_err(e: rusqlite::Error) -> String {
+    format!("history database fail: {}", e)
+}
+
+#[derive(Debug)]
+pub struct History {
+    conn: Mutex<Connection>,
+}
+
+impl History {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let conn = Connection::open(path).map_err(db_err)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(db_err)?;
+        conn.execute_batch(SCHEMA).map_err(db_err)?;
+        Ok(History {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn record(&self, meta: &RunMeta, report: &RunReport) -> Result<(), String> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(db_err)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO runs VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                report.run_id,
+                meta.workflow_id,
+                meta.revision,
+                meta.trigger,
+                report.status,
+                report.started_at,
+                millis(&report.started_at)?,
+                report.ended_at,
+                report.artifact_dir,
+                serde_json::to_string(&report.checks).unwrap_or_default(),
+            ],
+        )
+        .map_err(db_err)?;
+        for (seq, node) in report.nodes.iter().enumerate() {
+            tx.execute(
+                "INSERT OR REPLACE INTO nodes VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    report.run_id,
+                    seq as i64,
+                    node.node_id,
+                    node.node_type,
+                    node.node_name,
+                    node.result,
+                    node.started_at,
+                    node.elapsed_ms as i64,
+                    serde_json::to_string(&node.logs).unwrap_or_default(),
+                    serde_json::to_string(&node.artifacts).unwrap_or_default(),
+                ],
+            )
+            .map_err(db_err)?;
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// Lists runs newest first, every filter field is optional.
+    pub fn query(&self, filter: &RunFilter) -> Result<Vec<RunRecord>, String> {
+        let mut clauses = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(workflow_id) = &filter.workflow_id {
+            clauses.push("workflow_id = ?");
+            values.push(workflow_id.clone().into());
+        }
+        if let Some(status) = &filter.status {
+            clauses.push("status = ?");
+            values.push(status.clone().into());
+        }
+        if let Some(from) = &filter.from {
+            clauses.push("started_ms >= ?");
+            values.push(millis(from)?.into());
+        }
+        if let Some(to) = &filter.to {
+            clauses.push("started_ms < ?");
+            values.push(millis(to)?.into());
+        }
+        let condition = match clauses.is_empty() {
+            true => String::new(),
+            false => format!("WHERE {}", clauses.join(" AND ")),
+        };
+        let sql = format!(
+            "SELECT run_id, workflow_id, revision, trigger, status, started_at, ended_at, artifact_dir \
+             FROM runs {} ORDER BY started_ms DESC LIMIT {}",
+            condition,
+            filter.limit.unwrap_or(100)
+        );
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql).map_err(db_err)?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(RunRecord {
+                    run_id: row.get(0)?,
+                    workflow_id: row.get(1)?,
+                    revision: row.get(2)?,
+                    trigger: row.get(3)?,
+                    status: row.get(4)?,
+                    started_at: row.get(5)?,
+                    ended_at: row.get(6)?,
+                    artifact_dir: row.get(7)?,
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+
+    pub fn get(&self, run_id: &str) -> Result<RunDetail, String> {
+        let conn = self.conn.lock().unwrap();
+        let (record, checks) = conn
+            .query_row(
+                "SELECT run_id, workflow_id, revision, trigger, status, started_at, ended_at, artifact_dir, checks \
+                 FROM runs WHERE run_id = ?1",
+                params![run_id],
+                |row| {
+                    let record = RunRecord {
+                        run_id: row.get(0)?,
+                        workflow_id: row.get(1)?,
+                        revision: row.get(2)?,
+                        trigger: row.get(3)?,
+                        status: row.get(4)?,
+                        started_at: row.get(5)?,
+                        ended_at: row.get(6)?,
+                        artifact_dir: row.get(7)?,
+                    };
+                    Ok((record, row.get::<_, String>(8)?))
+                },
+            )
+            .map_err(|_| format!("run not found: {}", run_id))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT node_id, node_type, node_name, result, started_at, elapsed_ms, logs, artifacts \
+                 FROM nodes WHERE run_id = ?1 ORDER BY seq",
+            )
+            .map_err(db_err)?;
+        let nodes = stmt
+            .query_map(params![run_id], |row| {
+                Ok(NodeReport {
+                    node_id: row.get(0)?,
+                    node_type: row.get(1)?,
+                    node_name: row.get(2)?,
+                    result: row.get(3)?,
+                    started_at: row.get(4)?,
+                    elapsed_ms: row.get::<_, i64>(5)? as u64,
+                    logs: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+                    artifacts: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+                })
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        let report = RunReport {
+            run_id: record.run_id.clone(),
+            status: record.status.clone(),
+            started_at: record.started_at.clone(),
+            ended_at: record.ended_at.clone(),
+            artifact_dir: record.artifact_dir.clone(),
+            nodes,
+            checks: serde_json::from_str::<Vec<CheckReport>>(&checks).unwrap_or_default(),
+        };
+        Ok(RunDetail { record, report })
+    }
+
+    /// Deletes runs older than `max_age_days` and, per workflow, beyond the
+    /// newest `keep_last`. Returns how many runs were removed.
+    pub fn purge(&self, retention: &Retention) -> Result<usize, String> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(db_err)?;
+
+        let mut doomed: Vec<(String, String)> = Vec::new();
+        {
+            let mut collect = |sql: &str, value: i64| -> Result<(), String> {
+                let mut stmt = tx.prepare(sql).map_err(db_err)?;
+                let rows = stmt
+                    .query_map(params![value], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map_err(db_err)?;
+                for row in rows {
+                    let row = row.map_err(db_err)?;
+                    if !doomed.contains(&row) {
+                        doomed.push(row);
+                    }
+                }
+                Ok(())
+            };
+            if let Some(days) = retention.max_age_days {
+                let cutoff = Utc::now() - ChronoDuration::days(days as i64);
+                collect(
+                    "SELECT run_id, artifact_dir FROM runs WHERE started_ms < ?1",
+                    cutoff.timestamp_millis(),
+                )?;
+            }
+            if let Some(keep) = retention.keep_last {
+                collect(
+                    "SELECT run_id, artifact_dir FROM (
+                        SELECT run_id, artifact_dir, ROW_NUMBER() OVER (
+                            PARTITION BY IFNULL(workflow_id, '') ORDER BY started_ms DESC
+                        ) AS rank FROM runs
+                    ) WHERE rank > ?1",
+                    keep as i64,
+                )?;
+            }
+        }
+
+        for (run_id, _) in &doomed {
+            tx.execute("DELETE FROM runs WHERE run_id = ?1", params![run_id])
+                .map_err(db_err)?;
+        }
+        tx.commit().map_err(db_err)?;
+
+        if retention.remove_artifacts {
+            for (_, dir) in &doomed {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+        Ok(doomed.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory() -> History {
+        History::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn meta(workflow_id: Option<&str>) -> RunMeta {
+        RunMeta {
+            workflow_id: workflow_id.map(str::to_string),
+            revision: workflow_id.map(|_| 2),
+            trigger: "manual".to_string(),
+        }
+    }
+
+    fn report(run_id: &str, status: &str, started: DateTime<Utc>) -> RunReport {
+        RunReport {
+            run_id: run_id.to_string(),
+            status: status.to_string(),
+            started_at: started.to_rfc3339(),
+            ended_at: (started + ChronoDuration::seconds(5)).to_rfc3339(),
+            artifact_dir: format!("runs/{}", run_id),
+            nodes: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    fn ids(records: Vec<RunRecord>) -> Vec<String> {
+        records.into_iter().map(|r| r.run_id).collect()
+    }
+
+    #[test]
+    fn record_then_query_filters_newest_first() {
+        let history = memory();
+        let now = Utc::now();
+        let runs = [
+            (
+                "r1",
+                Some("wf-a"),
+                "success",
+                now - ChronoDuration::hours(3),
+            ),
+            (
+                "r2",
+                Some("wf-a"),
+                "failure",
+                now - ChronoDuration::hours(2),
+            ),
+            (
+                "r3",
+                Some("wf-b"),
+                "success",
+                now - ChronoDuration::hours(1),
+            ),
+            ("r4", None, "success", now),
+        ];
+        for (id, workflow_id, status, started) in runs {
+            history
+                .record(&meta(workflow_id), &report(id, status, started))
+                .unwrap();
+        }
+
+        let all = history.query(&RunFilter::default()).unwrap();
+        assert_eq!(ids(all), vec!["r4", "r3", "r2", "r1"]);
+
+        let filter = RunFilter {
+            workflow_id: Some("wf-a".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(history.query(&filter).unwrap()), vec!["r2", "r1"]);
+
+        let filter = RunFilter {
+            status: Some("success".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(ids(history.query(&filter).unwrap()), vec!["r4", "r3"]);
+
+        let filter = RunFilter {
+            from: Some((now - ChronoDuration::minutes(150)).to_rfc3339()),
+            to: Some((now - ChronoDuration::minutes(30)).to_rfc3339()),
+            ..Default::default()
+        };
+        assert_eq!(ids(history.query(&filter).unwrap()), vec!["r3", "r2"]);
+
+        let filter = RunFilter {
+            from: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+        assert!(history.query(&filter).is_err());
+    }
+
+    #[test]
+    fn record_then_get_round_trips_nodes_and_checks() {
+        let history = memory();
+        let mut run = report("r1", "failure", Utc::now());
+        run.push(NodeReport {
+            node_id: "b".to_string(),
+            node_type: "operate".to_string(),
+            node_name: "open_web".to_string(),
+            result: "Failure".to_string(),
+            started_at: run.started_at.clone(),
+            elapsed_ms: 1200,
+            logs: vec!["open https://a.test".to_string()],
+            artifacts: vec!["runs/r1/b.png".to_string()],
+        });
+        run.push_check(CheckReport {
+            node_id: "b".to_string(),
+            passed: false,
+            message: "title mismatch".to_string(),
+        });
+        history.record(&meta(Some("wf-a")), &run).unwrap();
+
+        let detail = history.get("r1").unwrap();
+        assert_eq!(detail.record.workflow_id.as_deref(), Some("wf-a"));
+        assert_eq!(detail.record.revision, Some(2));
+        assert_eq!(detail.report.status, "failure");
+        assert_eq!(detail.report.nodes.len(), 1);
+        assert_eq!(detail.report.nodes[0].elapsed_ms, 1200);
+        assert_eq!(detail.report.nodes[0].logs, run.nodes[0].logs);
+        assert_eq!(detail.report.nodes[0].artifacts, run.nodes[0].artifacts);
+        assert_eq!(detail.report.checks[0].message, "title mismatch");
+        assert!(history.get("r2").is_err());
+    }
+
+    #[test]
+    fn purge_keeps_the_newest_per_workflow() {
+        let history = memory();
+        let now = Utc::now();
+        for (n, workflow_id) in ["wf-a", "wf-a", "wf-a", "wf-b", "wf-b"].iter().enumerate() {
+            let started = now - ChronoDuration::minutes(10 - n as i64);
+            let run = report(&format!("r{}", n), "success", started);
+            history.record(&meta(Some(workflow_id)), &run).unwrap();
+        }
+        history
+            .record(&meta(None), &report("adhoc", "success", now))
+            .unwrap();
+
+        let retention = Retention {
+            keep_last: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(history.purge(&retention).unwrap(), 3);
+        let left = ids(history.query(&RunFilter::default()).unwrap());
+        assert_eq!(left, vec!["adhoc", "r4", "r2"]);
+    }
+
+    #[test]
+    fn purge_removes_old_runs_and_their_nodes() {
+        let history = memory();
+        let now = Utc::now();
+        let mut old = report("old", "success", now - ChronoDuration::days(40));
+        old.push(NodeReport::default());
+        history.record(&meta(None), &old).unwrap();
+        history
+            .record(&meta(None), &report("new", "success", now))
+            .unwrap();
+
+        let retention = Retention {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(history.purge(&retention).unwrap(), 1);
+        assert_eq!(
+            ids(history.query(&RunFilter::default()).unwrap()),
+            vec!["new"]
+        );
+        let conn = history.conn.lock().unwrap();
+        let nodes: i64 = conn
+            .query_row("SELECT COUNT(*) FROM nodes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(nodes, 0);
+    }
+}
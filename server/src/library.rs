@@ -0,0 +1,407 @@
+use base64::prelude::*;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::misc;
+use crate::workflow::{self, Change};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkflowDraft {
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub source: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub revision: u32,
+}
+
+/// An immutable snapshot written on every save that changes the content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub revision: u32,
+    pub hash: String,
+    pub created_at: String,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionSummary {
+    pub revision: u32,
+    pub hash: String,
+    pub created_at: String,
+    pub name: String,
+}
+
+impl Revision {
+    pub fn script(&self) -> String {
+        BASE64_STANDARD.encode(&self.source)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowSummary {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub revision: u32,
+}
+
+impl Workflow {
+    /// The base64 form `run_workflow` and the scheduler expect.
+    pub fn script(&self) -> String {
+        BASE64_STANDARD.encode(&self.source)
+    }
+
+    fn summary(&self) -> WorkflowSummary {
+        WorkflowSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            tags: self.tags.clone(),
+            created_at: self.created_at.clone(),
+            updated_at: self.updated_at.clone(),
+            revision: self.revision,
+        }
+    }
+}
+
+fn workflow_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    if !misc::is_safe_name(id) {
+        return Err(format!("invalid workflow id: {}", id));
+    }
+    Ok(dir.join(format!("{}.json", id)))
+}
+
+// one json file per workflow, the lock keeps concurrent commands from interleaving writes
+#[derive(Debug)]
+pub struct Library {
+    dir: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl Library {
+    pub fn new(dir: PathBuf) -> Self {
+        Library {
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self, id: &str) -> Result<Workflow, String> {
+        let path = workflow_path(&self.dir, id)?;
+        let raw = fs::read_to_string(path).map_err(|_| format!("workflow not found: {}", id))?;
+        serde_json::from_str(&raw).map_err(|e| format!("workflow {} is corrupted: {}", id, e))
+    }
+
+    fn revision_dir(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.revisions", id))
+    }
+
+    fn read_revisions(&self, id: &str) -> Vec<Revision> {
+        let mut revisions: Vec<Revision> = fs::read_dir(self.revision_dir(id))
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_else(|_| Vec::new())
+            .into_iter()
+            .filter_map(|path: PathBuf| fs::read_to_string(path).ok())
+            .filter_map(|raw| serde_json::from_str(&raw).ok())
+            .collect();
+        revisions.sort_by_key(|r| r.revision);
+        revisions
+    }
+
+    // saving unchanged content keeps the current revision instead of adding a duplicate
+    fn write(&self, workflow: &mut Workflow) -> Result<(), String> {
+        let latest = self.read_revisions(&workflow.id).pop();
+        let hash = workflow::content_hash(&workflow.source);
+        let revision = match latest {
+            Some(latest)
+                if latest.hash == hash
+                    && latest.name == workflow.name
+                    && latest.description == workflow.description
+                    && latest.tags == workflow.tags =>
+            {
+                latest.revision
+            }
+            latest => {
+                let revision = Revision {
+                    revision: latest.map_or(1, |r| r.revision + 1),
+                    hash,
+                    created_at: workflow.updated_at.clone(),
+                    name: workflow.name.clone(),
+                    description: workflow.description.clone(),
+                    tags: workflow.tags.clone(),
+                    source: workflow.source.clone(),
+                };
+                self.write_revision(&workflow.id, &revision)?;
+                revision.revision
+            }
+        };
+        workflow.revision = revision;
+        self.write_current(workflow)
+    }
+
+    fn write_revision(&self, id: &str, revision: &Revision) -> Result<(), String> {
+        let save = || -> Result<(), Box<dyn Error>> {
+            let dir = self.revision_dir(id);
+            fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("{}.json", revision.revision));
+            fs::write(path, serde_json::to_string_pretty(revision)?)?;
+            Ok(())
+        };
+        save().map_err(|e| format!("save revision fail: {}", e))
+    }
+
+    fn write_current(&self, workflow: &Workflow) -> Result<(), String> {
+        let save = || -> Result<(), Box<dyn Error>> {
+            fs::create_dir_all(&self.dir)?;
+            let path = workflow_path(&self.dir, &workflow.id)?;
+            fs::write(path, serde_json::to_string_pretty(workflow)?)?;
+            Ok(())
+        };
+        save().map_err(|e| format!("save workflow fail: {}", e))
+    }
+
+    fn new_id(&self) -> String {
+        let base = format!("wf-{}", Utc::now().format("%Y%m%d%H%M%S%3f"));
+        let mut id = base.clone();
+        let mut n = 1;
+        while self.dir.join(format!("{}.json", id)).exists() {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        id
+    }
+
+    pub fn create(&self, draft: WorkflowDraft) -> Result<Workflow, String> {
+        if draft.name.trim().is_empty() {
+            return Err("workflow name is empty".to_string());
+        }
+        let _guard = self.lock.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+        let mut workflow = Workflow {
+            id: self.new_id(),
+            name: draft.name.trim().to_string(),
+            description: draft.description,
+            tags: draft.tags,
+            source: draft.source,
+            created_at: now.clone(),
+            updated_at: now,
+            revision: 0,
+        };
+        self.write(&mut workflow)?;
+        Ok(workflow)
+    }
+
+    /// Lists every stored workflow, most recently updated first.
+    pub fn list(&self) -> Vec<WorkflowSummary> {
+        let mut items: Vec<Workflow> = fs::read_dir(&self.dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_else(|_| Vec::new())
+            .into_iter()
+            .filter(|path: &PathBuf| path.extension().is_some_and(|e| e == "json"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .filter_map(|raw| serde_json::from_str(&raw).ok())
+            .collect();
+        items.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        items.iter().map(Workflow::summary).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Result<Workflow, String> {
+        self.read(id)
+    }
+
+    pub fn update(&self, id: &str, draft: WorkflowDraft) -> Result<Workflow, String> {
+        if draft.name.trim().is_empty() {
+            return Err("workflow name is empty".to_string());
+        }
+        let _guard = self.lock.lock().unwrap();
+        let mut workflow = Workflow {
+            name: draft.name.trim().to_string(),
+            description: draft.description,
+            tags: draft.tags,
+            source: draft.source,
+            updated_at: Utc::now().to_rfc3339(),
+            ..self.read(id)?
+        };
+        self.write(&mut workflow)?;
+        Ok(workflow)
+    }
+
+    pub fn duplicate(&self, id: &str, name: Option<&str>) -> Result<Workflow, String> {
+        let origin = self.read(id)?;
+        self.create(WorkflowDraft {
+            name: name
+                .map(String::from)
+                .unwrap_or(format!("{} copy", origin.name)),
+            description: origin.description,
+            tags: origin.tags,
+            source: origin.source,
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap();
+        let path = workflow_path(&self.dir, id)?;
+        fs::remove_file(path).map_err(|_| format!("workflow not found: {}", id))?;
+        let _ = fs::remove_dir_all(self.revision_dir(id));
+        Ok(())
+    }
+
+    pub fn revisions(&self, id: &str) -> Result<Vec<RevisionSummary>, String> {
+        workflow_path(&self.dir, id)?;
+        Ok(self
+            .read_revisions(id)
+            .into_iter()
+            .rev()
+            .map(|r| RevisionSummary {
+                revision: r.revision,
+                hash: r.hash,
+                created_at: r.created_at,
+                name: r.name,
+            })
+            .collect())
+    }
+
+    pub fn revision(&self, id: &str, revision: u32) -> Result<Revision, String> {
+        workflow_path(&self.dir, id)?;
+        self.read_revisions(id)
+            .into_iter()
+            .find(|r| r.revision == revision)
+            .ok_or(format!("revision {} not found for {}", revision, id))
+    }
+
+    pub fn diff(&self, id: &str, from: u32, to: u32) -> Result<Vec<Change>, String> {
+        let parse = |revision: u32| -> Result<workflow::Flow, String> {
+            let revision = self.revision(id, revision)?;
+            workflow::parse(&revision.source).map_err(|e| e.to_string())
+        };
+        Ok(workflow::diff(&parse(from)?, &parse(to)?))
+    }
+
+    /// Restoring saves the old content as a new revision, history is never rewritten.
+    pub fn restore(&self, id: &str, revision: u32) -> Result<Workflow, String> {
+        let revision = self.revision(id, revision)?;
+        self.update(
+            id,
+            WorkflowDraft {
+                name: revision.name,
+                description: revision.description,
+                tags: revision.tags,
+                source: revision.source,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"
+        flowchart TD
+        a["name: head, type: control"]
+        b["name: end, type: control"]
+        a -->|success| b
+    "#;
+
+    const V2: &str = r#"
+        flowchart TD
+        a["name: head, type: control"]
+        b["name: end, type: control"]
+        c["name: sleep, type: control, para: {secs: '1'}"]
+        a -->|success| c
+        c -->|success| b
+    "#;
+
+    fn library(name: &str) -> Library {
+        let dir =
+            std::env::temp_dir().join(format!("mashu-library-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Library::new(dir)
+    }
+
+    fn draft(name: &str, source: &str) -> WorkflowDraft {
+        WorkflowDraft {
+            name: name.to_string(),
+            source: source.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn saves_keep_revisions_and_restore_adds_one() {
+        let library = library("revisions");
+        let created = library.create(draft(" login ", V1)).unwrap();
+        assert_eq!(created.name, "login");
+        assert_eq!(created.revision, 1);
+
+        let updated = library.update(&created.id, draft("login", V2)).unwrap();
+        assert_eq!(updated.revision, 2);
+        assert_eq!(updated.created_at, created.created_at);
+
+        // saving the same content again is not a new revision
+        let same = library.update(&created.id, draft("login", V2)).unwrap();
+        assert_eq!(same.revision, 2);
+        assert_eq!(library.revisions(&created.id).unwrap().len(), 2);
+        assert!(!library.diff(&created.id, 1, 2).unwrap().is_empty());
+
+        let restored = library.restore(&created.id, 1).unwrap();
+        assert_eq!(restored.revision, 3);
+        assert_eq!(restored.source, V1);
+        let revisions: Vec<u32> = library
+            .revisions(&created.id)
+            .unwrap()
+            .iter()
+            .map(|r| r.revision)
+            .collect();
+        assert_eq!(revisions, vec![3, 2, 1]);
+        assert_eq!(library.get(&created.id).unwrap().source, V1);
+    }
+
+    #[test]
+    fn duplicate_and_delete() {
+        let library = library("copies");
+        let origin = library.create(draft("login", V1)).unwrap();
+        let copy = library.duplicate(&origin.id, None).unwrap();
+        assert_ne!(copy.id, origin.id);
+        assert_eq!(copy.name, "login copy");
+        assert_eq!(copy.source, origin.source);
+        assert_eq!(library.list().len(), 2);
+
+        library.delete(&origin.id).unwrap();
+        assert!(library.get(&origin.id).is_err());
+        assert!(library.revisions(&origin.id).unwrap().is_empty());
+        assert!(library.delete(&origin.id).is_err());
+        assert_eq!(library.list().len(), 1);
+    }
+
+    #[test]
+    fn refuses_empty_names_and_unsafe_ids() {
+        let library = library("refusals");
+        assert!(library.create(draft("  ", V1)).is_err());
+        assert!(library.get("../secrets").is_err());
+        assert!(library.delete("../secrets").is_err());
+    }
+}
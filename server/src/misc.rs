@@ -1,17 +1,554 @@
+use encoding_rs::{Encoding, UTF_8};
+use regex::bytes::Regex;
 use reqwest;
+use reqwest::header::{CONTENT_TYPE, COOKIE, USER_AGENT};
+use reqwest::{redirect, Method};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
 use std::time::Duration;
-use tokio::time::timeout;
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub json: Option<String>,
+    pub form: Vec<(String, String)>,
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FetchOption {
+    pub method: String,
+    pub headers: BTreeMap<String, String>,
+    pub timeout_ms: u64,
+    pub max_body_bytes: usize,
+    pub follow_redirects: bool,
+    pub user_agent: Option<String>,
+    pub cookies: BTreeMap<String, String>,
+}
+
+impl Default for FetchOption {
+    fn default() -> Self {
+        FetchOption {
+            method: "GET".to_string(),
+            headers: BTreeMap::new(),
+            timeout_ms: 5000,
+            max_body_bytes: 10 * 1024 * 1024,
+            follow_redirects: true,
+            user_agent: None,
+            cookies: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub final_url: String,
+    pub headers: BTreeMap<String, String>,
+    pub encoding: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FetchError {
+    Invalid { message: String },
+    Timeout { message: String },
+    Connect { message: String },
+    Tls { message: String },
+    Status { status: u16, final_url: String },
+    Body { message: String },
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::Invalid { message } => write!(f, "Invalid request: {}", message),
+            FetchError::Timeout { message } => write!(f, "Request timed out: {}", message),
+            FetchError::Connect { message } => write!(f, "Connection failed: {}", message),
+            FetchError::Tls { message } => write!(f, "TLS failed: {}", message),
+            FetchError::Status { status, final_url } => {
+                write!(f, "Failed to fetch the page: {} {}", status, final_url)
+            }
+            FetchError::Body { message } => write!(f, "Failed to read the body: {}", message),
+        }
+    }
+}
+
+// a tls failure surfaces as the backend's own error type somewhere in the chain,
+// io errors hide their inner error from `source()` so it is taken via `get_ref()`
+fn is_tls(e: &reqwest::Error) -> bool {
+    let tls = |cause: &(dyn Error + 'static)| {
+        cause.is::<native_tls::Error>() || cause.is::<rustls::Error>()
+    };
+    let mut source = e.source();
+    while let Some(cause) = source {
+        if tls(cause) {
+            return true;
+        }
+        if let Some(inner) = cause
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io| io.get_ref())
+        {
+            if tls(inner) {
+                return true;
+            }
+        }
+        source = cause.source();
+    }
+    false
+}
+
+// reqwest folds dns, connect and handshake failures into one connect error,
+// the underlying cause is only visible in the source chain
+fn classify(e: reqwest::Error) -> FetchError {
+    let (timeout, tls) = (e.is_timeout(), is_tls(&e));
+    let (connect, body) = (
+        e.is_connect() || e.is_request(),
+        e.is_body() || e.is_decode(),
+    );
+
+    let e = e.without_url();
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message = format!("{}: {}", message, cause);
+        source = cause.source();
+    }
+
+    if timeout {
+        FetchError::Timeout { message }
+    } else if tls {
+        FetchError::Tls { message }
+    } else if connect {
+        FetchError::Connect { message }
+    } else if body {
+        FetchError::Body { message }
+    } else {
+        FetchError::Invalid { message }
+    }
+}
+
+// the content-type charset wins, then a <meta charset> within the first 1024 bytes
+fn detect_encoding(content_type: Option<&str>, body: &[u8]) -> &'static Encoding {
+    let from_header = content_type
+        .and_then(|ct| {
+            ct.split(';')
+                .find_map(|p| p.trim().strip_prefix("charset="))
+        })
+        .and_then(|label| Encoding::for_label(label.trim_matches('"').as_bytes()));
+    if let Some(encoding) = from_header {
+        return encoding;
+    }
+
+    let head = &body[..body.len().min(1024)];
+    let re = Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([\w-]+)"#)
+        .expect("invalid charset pattern");
+    re.captures(head)
+        .and_then(|cap| Encoding::for_label(&cap[1]))
+        .unwrap_or(UTF_8)
+}
+
+pub async fn fetch(url: &str, option: &FetchOption) -> Result<FetchResponse, FetchError> {
+    let invalid = |message: String| FetchError::Invalid { message };
+    let method = Method::from_bytes(option.method.to_uppercase().as_bytes())
+        .map_err(|_| invalid(format!("invalid method: {}", option.method)))?;
+    let policy = if option.follow_redirects {
+        redirect::Policy::limited(10)
+    } else {
+        redirect::Policy::none()
+    };
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(option.timeout_ms))
+        .redirect(policy)
+        .build()
+        .map_err(classify)?;
+
+    let mut builder = client.request(method, url);
+    for (key, value) in &option.headers {
+        builder = builder.header(key, value);
+    }
+    if let Some(agent) = &option.user_agent {
+        builder = builder.header(USER_AGENT, agent);
+    }
+    if !option.cookies.is_empty() {
+        let cookies: Vec<String> = option
+            .cookies
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        builder = builder.header(COOKIE, cookies.join("; "));
+    }
+
+    let mut response = builder.send().await.map_err(classify)?;
+    let status = response.status();
+    let final_url = response.url().to_string();
+    if status.is_client_error() || status.is_server_error() {
+        return Err(FetchError::Status {
+            status: status.as_u16(),
+            final_url,
+        });
+    }
+
+    let headers: BTreeMap<String, String> = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(classify)? {
+        if body.len() + chunk.len() > option.max_body_bytes {
+            return Err(FetchError::Body {
+                message: format!("body exceeds {} bytes", option.max_body_bytes),
+            });
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let content_type = headers.get(CONTENT_TYPE.as_str()).map(String::as_str);
+    let encoding = detect_encoding(content_type, &body);
+    let (text, _, _) = encoding.decode(&body);
+
+    Ok(FetchResponse {
+        status: status.as_u16(),
+        final_url,
+        headers,
+        encoding: encoding.name().to_string(),
+        body: text.into_owned(),
+    })
+}
 
 pub async fn http_get(url: &str) -> Result<String, String> {
-    let response = timeout(Duration::from_secs(5), reqwest::get(url))
+    fetch(url, &FetchOption::default())
         .await
-        .map_err(|_| "Request timed out".to_string())?
+        .map(|response| response.body)
+        .map_err(|e| e.to_string())
+}
+
+pub async fn http_request(req: &HttpRequest) -> Result<HttpResponse, String> {
+    let method = Method::from_bytes(req.method.to_uppercase().as_bytes())
+        .map_err(|_| format!("invalid method: {}", req.method))?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(req.timeout_ms))
+        .build()
         .map_err(|e| e.to_string())?;
 
-    if response.status().is_success() {
-        let body = response.text().await.map_err(|e| e.to_string())?;
-        return Ok(body);
+    let mut builder = client.request(method, &req.url).query(&req.query);
+    for (key, value) in &req.headers {
+        builder = builder.header(key, value);
+    }
+    if let Some(json) = &req.json {
+        let body: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("invalid json body: {}", e))?;
+        builder = builder.json(&body);
+    } else if !req.form.is_empty() {
+        builder = builder.form(&req.form);
+    }
+
+    let response = builder.send().await.map_err(|e| {
+        if e.is_timeout() {
+            "Request timed out".to_string()
+        } else {
+            e.to_string()
+        }
+    })?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let body = response.text().await.map_err(|e| e.to_string())?;
+
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+// accepts exact codes, classes and ranges separated by commas, e.g. "2xx, 304, 400-404"
+pub fn status_match(pattern: &str, status: u16) -> bool {
+    pattern.split(',').map(str::trim).any(|p| {
+        if let Some(class) = p.strip_suffix("xx") {
+            return class.parse::<u16>().is_ok_and(|c| status / 100 == c);
+        }
+        if let Some((low, high)) = p.split_once('-') {
+            return match (low.trim().parse::<u16>(), high.trim().parse::<u16>()) {
+                (Ok(low), Ok(high)) => (low..=high).contains(&status),
+                _ => false,
+            };
+        }
+        p.parse::<u16>().is_ok_and(|code| code == status)
+    })
+}
+
+/// Names that become file names (profiles, workflows, runs, artifacts) are limited
+/// to letters, digits, `-` and `_`, so they can never point outside their directory.
+pub fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+
+    // answers a single request with `response` after `delay` and hands back what it received
+    fn mock_server<R>(response: R, delay: Duration) -> (String, Receiver<String>)
+    where
+        R: AsRef<[u8]> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut head, mut length) = (String::new(), 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let _ = tx.send(format!("{}\r\n{}", head, String::from_utf8_lossy(&body)));
+            thread::sleep(delay);
+            let _ = stream.write_all(response.as_ref());
+        });
+        (url, rx)
+    }
+
+    const OK: &str =
+        "HTTP/1.1 201 Created\r\nX-Mock: yes\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+
+    // a complete response with the length filled in, `head` is the status line and headers
+    fn reply(head: &str, body: &[u8]) -> Vec<u8> {
+        let head = format!(
+            "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            head,
+            body.len()
+        );
+        [head.as_bytes(), body].concat()
+    }
+
+    fn request(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: "get".to_string(),
+            url: url.to_string(),
+            timeout_ms: 2000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn status_match_accepts_codes_classes_and_ranges() {
+        assert!(status_match("200", 200));
+        assert!(!status_match("200", 201));
+        assert!(status_match("2xx", 204));
+        assert!(!status_match("2xx", 302));
+        assert!(status_match("400-404", 404));
+        assert!(!status_match("400-404", 405));
+        assert!(status_match("2xx, 304", 304));
+        assert!(!status_match("abc, 5x", 500));
+    }
+
+    #[tokio::test]
+    async fn http_request_sends_method_query_and_headers() {
+        let (url, received) = mock_server(OK, Duration::ZERO);
+        let response = http_request(&HttpRequest {
+            method: "delete".to_string(),
+            query: vec![("q".to_string(), "a b".to_string())],
+            headers: vec![("X-Token".to_string(), "secret".to_string())],
+            ..request(&format!("{}/items", url))
+        })
+        .await
+        .unwrap();
+
+        let raw = received.recv().unwrap();
+        assert!(raw.starts_with("DELETE /items?q=a+b HTTP/1.1"));
+        assert!(raw.to_lowercase().contains("x-token: secret"));
+        assert_eq!(response.status, 201);
+        assert_eq!(response.headers["x-mock"], "yes");
+        assert_eq!(response.body, "ok");
+    }
+
+    #[tokio::test]
+    async fn http_request_sends_json_body() {
+        let (url, received) = mock_server(OK, Duration::ZERO);
+        http_request(&HttpRequest {
+            method: "post".to_string(),
+            json: Some(r#"{"name": "mashu"}"#.to_string()),
+            ..request(&url)
+        })
+        .await
+        .unwrap();
+
+        let raw = received.recv().unwrap();
+        assert!(raw
+            .to_lowercase()
+            .contains("content-type: application/json"));
+        assert!(raw.ends_with(r#"{"name":"mashu"}"#));
     }
 
-    Err(format!("Failed to fetch the page: {}", response.status()))
+    #[tokio::test]
+    async fn http_request_sends_form_body() {
+        let (url, received) = mock_server(OK, Duration::ZERO);
+        http_request(&HttpRequest {
+            method: "post".to_string(),
+            form: vec![
+                ("user".to_string(), "a&b".to_string()),
+                ("n".to_string(), "1".to_string()),
+            ],
+            ..request(&url)
+        })
+        .await
+        .unwrap();
+
+        let raw = received.recv().unwrap();
+        assert!(raw
+            .to_lowercase()
+            .contains("content-type: application/x-www-form-urlencoded"));
+        assert!(raw.ends_with("user=a%26b&n=1"));
+    }
+
+    #[tokio::test]
+    async fn http_request_rejects_bad_input() {
+        let invalid = HttpRequest {
+            method: "not a method".to_string(),
+            ..request("http://127.0.0.1:1")
+        };
+        let error = http_request(&invalid).await.unwrap_err();
+        assert!(error.starts_with("invalid method"));
+
+        let json = HttpRequest {
+            json: Some("{oops".to_string()),
+            ..request("http://127.0.0.1:1")
+        };
+        let error = http_request(&json).await.unwrap_err();
+        assert!(error.starts_with("invalid json body"));
+    }
+
+    #[tokio::test]
+    async fn http_request_times_out() {
+        let (url, _received) = mock_server(OK, Duration::from_millis(1000));
+        let error = http_request(&HttpRequest {
+            timeout_ms: 200,
+            ..request(&url)
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(error, "Request timed out");
+    }
+
+    #[tokio::test]
+    async fn fetch_reports_timeouts() {
+        let (url, _received) = mock_server(OK, Duration::from_millis(1000));
+        let option = FetchOption {
+            timeout_ms: 200,
+            ..Default::default()
+        };
+        let error = fetch(&url, &option).await.unwrap_err();
+        assert!(matches!(error, FetchError::Timeout { .. }), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn fetch_reports_refused_connections() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{}", port);
+        let error = fetch(&url, &FetchOption::default()).await.unwrap_err();
+        assert!(matches!(error, FetchError::Connect { .. }), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn fetch_reports_error_statuses() {
+        let (url, _received) = mock_server(reply("HTTP/1.1 404 Not Found", b""), Duration::ZERO);
+        let url = format!("{}/missing", url);
+        match fetch(&url, &FetchOption::default()).await {
+            Err(FetchError::Status { status, final_url }) => {
+                assert_eq!(status, 404);
+                assert_eq!(final_url, url);
+            }
+            other => panic!("expected a status error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_refuses_bodies_over_the_limit() {
+        let (url, _received) = mock_server(reply("HTTP/1.1 200 OK", b"0123456789"), Duration::ZERO);
+        let option = FetchOption {
+            max_body_bytes: 4,
+            ..Default::default()
+        };
+        match fetch(&url, &option).await {
+            Err(FetchError::Body { message }) => assert_eq!(message, "body exceeds 4 bytes"),
+            other => panic!("expected a body error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_decodes_the_header_charset() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=iso-8859-1";
+        let (url, _received) = mock_server(reply(head, b"caf\xe9"), Duration::ZERO);
+        let response = fetch(&url, &FetchOption::default()).await.unwrap();
+        assert_eq!(response.encoding, "windows-1252");
+        assert_eq!(response.body, "caf\u{e9}");
+    }
+
+    #[tokio::test]
+    async fn fetch_decodes_the_meta_charset() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/html";
+        let body = [
+            b"<meta charset='shift_jis'><p>".as_slice(),
+            b"\x93\xfa\x96\x7b",
+        ]
+        .concat();
+        let (url, _received) = mock_server(reply(head, &body), Duration::ZERO);
+        let response = fetch(&url, &FetchOption::default()).await.unwrap();
+        assert_eq!(response.encoding, "Shift_JIS");
+        assert!(response.body.ends_with("\u{65e5}\u{672c}"));
+    }
+
+    #[tokio::test]
+    async fn fetch_can_stop_at_redirects() {
+        let head = "HTTP/1.1 302 Found\r\nLocation: /elsewhere";
+        let (url, _received) = mock_server(reply(head, b""), Duration::ZERO);
+        let option = FetchOption {
+            follow_redirects: false,
+            ..Default::default()
+        };
+        let response = fetch(&url, &option).await.unwrap();
+        assert_eq!(response.status, 302);
+        assert_eq!(response.headers["location"], "/elsewhere");
+        assert!(response.final_url.starts_with(&url));
+    }
 }
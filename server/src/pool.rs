@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use thirtyfour::prelude::*;
+use tokio::runtime::Runtime;
+
+use crate::driver::DriverProcess;
+use crate::task::WebOption;
+
+const CLEAR_STORAGE: &str = r#"
+    window.localStorage.clear();
+    window.sessionStorage.clear();
+"#;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolConfig {
+    pub max_size: usize,
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_size: 4,
+            idle_timeout_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResetPolicy {
+    Keep,
+    /// Clears cookies and storage of every origin the session's runs were seen on.
+    ClearCookies,
+}
+
+impl ResetPolicy {
+    pub fn parse(raw: &str) -> Self {
+        match raw {
+            "clear" | "clear_cookies" => ResetPolicy::ClearCookies,
+            _ => ResetPolicy::Keep,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PooledSession {
+    pub tag: String,
+    pub driver: WebDriver,
+    pub process: Option<DriverProcess>,
+    pub option: WebOption,
+    /// Origins visited while the session was leased, what `ClearCookies` has to clear.
+    pub origins: Vec<String>,
+    idle_since: Instant,
+}
+
+impl PooledSession {
+    pub fn new(
+        tag: &str,
+        driver: WebDriver,
+        process: Option<DriverProcess>,
+        option: WebOption,
+        origins: Vec<String>,
+    ) -> Self {
+        PooledSession {
+            tag: tag.to_string(),
+            driver,
+            process,
+            option,
+            origins,
+            idle_since: Instant::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub tag: String,
+    pub browser: String,
+    pub idle_secs: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct SessionPool {
+    sessions: Mutex<Vec<PooledSession>>,
+    config: Mutex<PoolConfig>,
+}
+
+fn quit(session: PooledSession) {
+    let rt = Runtime::new().expect("create runtime fail");
+    let _ = rt.block_on(async { session.driver.quit().await });
+}
+
+impl SessionPool {
+    pub fn new(config: PoolConfig) -> Self {
+        SessionPool {
+            sessions: Mutex::new(Vec::new()),
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> PoolConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn configure(&self, config: PoolConfig) {
+        *self.config.lock().unwrap() = config;
+        self.evict_idle();
+    }
+
+    /// Takes a warm session with the given tag and browser out of the pool.
+    pub fn borrow(&self, tag: &str, browser: &str) -> Option<PooledSession> {
+        self.evict_idle();
+        let mut sessions = self.sessions.lock().unwrap();
+        let index = sessions
+            .iter()
+            .position(|s| s.tag == tag && s.option.browser == browser)?;
+        Some(sessions.remove(index))
+    }
+
+    /// Puts a session back for later runs, or quits it when the pool is full.
+    pub fn give_back(&self, mut session: PooledSession, reset: ResetPolicy) {
+        // webdriver only reaches the cookies and storage of the open document,
+        // so each origin the session was seen on is opened and cleared in turn
+        if reset == ResetPolicy::ClearCookies {
+            let origins = std::mem::take(&mut session.origins);
+            let rt = Runtime::new().expect("create runtime fail");
+            let cleared = rt.block_on(async {
+                session.driver.delete_all_cookies().await?;
+                for origin in &origins {
+                    session.driver.goto(origin).await?;
+                    session.driver.delete_all_cookies().await?;
+                    session.driver.execute(CLEAR_STORAGE, Vec::new()).await?;
+                }
+                session.driver.goto("about:blank").await
+            });
+            // a session that may still hold another run's login is not handed out again
+            if let Err(e) = cleared {
+                println!("clear pooled session {} fail: {}", session.tag, e);
+                quit(session);
+                return;
+            }
+        }
+
+        let max_size = self.config().max_size;
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= max_size {
+            drop(sessions);
+            quit(session);
+            return;
+        }
+        session.idle_since = Instant::now();
+        sessions.push(session);
+    }
+
+    pub fn evict_idle(&self) {
+        let timeout = Duration::from_secs(self.config().idle_timeout_secs);
+        let expired: Vec<PooledSession> = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let (expired, alive) = sessions
+                .drain(..)
+                .partition(|s| s.idle_since.elapsed() >= timeout);
+            *sessions = alive;
+            expired
+        };
+        expired.into_iter().for_each(quit);
+    }
+
+    pub fn list(&self) -> Vec<SessionSummary> {
+        self.evict_idle();
+        self.sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|s| SessionSummary {
+                tag: s.tag.clone(),
+                browser: s.option.browser.clone(),
+                idle_secs: s.idle_since.elapsed().as_secs(),
+            })
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        let sessions: Vec<PooledSession> = self.sessions.lock().unwrap().drain(..).collect();
+        sessions.into_iter().for_each(quit);
+    }
+
+    pub fn start_reaper(self: &Arc<Self>) {
+        let pool = Arc::clone(self);
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(30));
+            pool.evict_idle();
+        });
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thirtyfour::prelude::*;
+
+use crate::misc;
+
+const READ_STORAGE: &str = r#"
+    const items = {};
+    for (let i = 0; i < window.localStorage.length; i++) {
+        const key = window.localStorage.key(i);
+        items[key] = window.localStorage.getItem(key);
+    }
+    return items;
+"#;
+
+const WRITE_STORAGE: &str = r#"
+    for (const [key, value] of Object.entries(arguments[0])) {
+        window.localStorage.setItem(key, value);
+    }
+"#;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OriginState {
+    pub origin: String,
+    pub cookies: Vec<Cookie>,
+    pub local_storage: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub origins: Vec<OriginState>,
+}
+
+fn profile_path(dir: &Path, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    if !misc::is_safe_name(name) {
+        return Err(format!("invalid profile name: {}", name).into());
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+impl Profile {
+    pub fn load(dir: &Path, name: &str) -> Result<Self, Box<dyn Error>> {
+        let raw = fs::read_to_string(profile_path(dir, name)?)?;
+        Ok(serde_json::from_str(&raw)?)
+    }
+
+    // a profile with the file missing is simply empty, saving fills it origin by origin
+    pub fn load_or_default(dir: &Path, name: &str) -> Self {
+        Profile::load(dir, name).unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let path = profile_path(dir, name)?;
+        fs::create_dir_all(dir)?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn merge(&mut self, state: OriginState) {
+        self.origins.retain(|o| o.origin != state.origin);
+        self.origins.push(state);
+    }
+}
+
+// about:blank, data: and file: pages have an opaque origin ("null") that
+// could never be visited again to restore it, so they are refused here
+pub async fn export_origin(driver: &WebDriver) -> Result<OriginState, String> {
+    let url = driver.current_url().await.map_err(|e| e.to_string())?;
+    let origin = url.origin();
+    if !origin.is_tuple() {
+        return Err(format!("{} has no origin to save", url));
+    }
+    let storage = driver
+        .execute(READ_STORAGE, Vec::new())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(OriginState {
+        origin: origin.ascii_serialization(),
+        cookies: driver.get_all_cookies().await.map_err(|e| e.to_string())?,
+        local_storage: storage.json().as_object().cloned().unwrap_or_default(),
+    })
+}
+
+// cookies and storage can only be written for the document's own origin,
+// so every origin is visited once before its state is restored
+pub async fn import_profile(driver: &WebDriver, profile: &Profile) -> WebDriverResult<()> {
+    // opaque origins saved by older versions can't be visited, they are skipped
+    for state in profile.origins.iter().filter(|s| s.origin != "null") {
+        driver.goto(&state.origin).await?;
+        for cookie in &state.cookies {
+            driver.add_cookie(cookie.clone()).await?;
+        }
+        if !state.local_storage.is_empty() {
+            let items = json!(state.local_storage);
+            driver.execute(WRITE_STORAGE, vec![items]).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckReport {
+    pub node_id: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeReport {
+    pub node_id: String,
+    pub node_type: String,
+    pub node_name: String,
+    pub result: String,
+    pub started_at: String,
+    pub elapsed_ms: u64,
+    pub logs: Vec<String>,
+    pub artifacts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunReport {
+    pub run_id: String,
+    pub status: String,
+    pub started_at: String,
+    pub ended_at: String,
+    pub artifact_dir: String,
+    pub nodes: Vec<NodeReport>,
+    pub checks: Vec<CheckReport>,
+}
+
+impl RunReport {
+    pub fn new(run_id: &str, artifact_dir: &Path) -> Self {
+        RunReport {
+            run_id: run_id.to_string(),
+            status: "running".to_string(),
+            started_at: Utc::now().to_rfc3339(),
+            artifact_dir: artifact_dir.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, node: NodeReport) {
+        self.nodes.push(node);
+    }
+
+    pub fn push_check(&mut self, check: CheckReport) {
+        self.checks.push(check);
+    }
+
+    pub fn failed_checks(&self) -> Vec<&CheckReport> {
+        self.checks.iter().filter(|c| !c.passed).collect()
+    }
+
+    pub fn finish(&mut self) {
+        let failed =
+            self.nodes.iter().any(|n| n.result == "Failure") || !self.failed_checks().is_empty();
+        self.status = if failed { "failure" } else { "success" }.to_string();
+        self.ended_at = Utc::now().to_rfc3339();
+    }
+
+    pub fn save(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let path = Path::new(&self.artifact_dir).join("report.json");
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+}
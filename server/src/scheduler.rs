@@ -0,0 +1,845 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::application::{self, ExecuteOption};
+use crate::history::RunMeta;
+use crate::task_helper;
+use crate::webhook;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CronPreview {
+    pub expression: String,
+    pub timezone: String,
+    pub summary: String,
+    pub next: Vec<String>,
+}
+
+// a readable summary of the fields, e.g. "at 21:32:00, every day" for `0 32 21 * * * *`
+fn cron_describe(expr: &str) -> String {
+    let names = [
+        "second",
+        "minute",
+        "hour",
+        "day of month",
+        "month",
+        "day of week",
+        "year",
+    ];
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let plain = |f: &&str| f.chars().all(|c| c.is_ascii_digit());
+
+    let mut parts = Vec::new();
+    let mut skip = 0;
+    if fields.len() >= 3 && fields[..3].iter().all(plain) {
+        parts.push(format!(
+            "at {:0>2}:{:0>2}:{:0>2}",
+            fields[2], fields[1], fields[0]
+        ));
+        skip = 3;
+    }
+    for (name, field) in names.iter().zip(&fields).skip(skip) {
+        match field.strip_prefix("*/") {
+            _ if *field == "*" || *field == "?" => continue,
+            Some(step) => parts.push(format!("{} every {}", name, step)),
+            None => parts.push(format!("{} {}", name, field)),
+        }
+    }
+
+    match parts.len() {
+        0 => "every second".to_string(),
+        1 if skip == 3 => format!("{}, every day", parts[0]),
+        _ => parts.join(", "),
+    }
+}
+
+/// Lists the next fire times the same way a `timing` node or a cron trigger computes them.
+pub fn preview_cron(expr: &str, tz: &str, count: usize) -> Result<CronPreview, String> {
+    let schedule = task_helper::cron_parse(expr)?;
+    let zone = task_helper::tz_parse(tz)?;
+
+    let mut next = Vec::new();
+    let mut after = Utc::now();
+    while next.len() < count {
+        let fire = match task_helper::next_fire(&schedule, zone, after) {
+            Some(fire) => fire,
+            None => break,
+        };
+        next.push(match zone {
+            Some(zone) => fire.with_timezone(&zone).to_rfc3339(),
+            None => fire.with_timezone(&Local).to_rfc3339(),
+        });
+        after = fire;
+    }
+
+    Ok(CronPreview {
+        expression: expr.to_string(),
+        timezone: zone.map_or("local".to_string(), |z| z.to_string()),
+        summary: cron_describe(expr),
+        next,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Trigger {
+    Cron {
+        expr: String,
+        tz: Option<String>,
+    },
+    Interval {
+        secs: u64,
+    },
+    FileWatch {
+        dir: String,
+        pattern: Option<String>,
+    },
+    Webhook {
+        port: u16,
+        path: Option<String>,
+    },
+}
+
+type Inputs = HashMap<String, String>;
+
+impl Trigger {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Trigger::Cron { expr, tz } => {
+                task_helper::cron_parse(expr)?;
+                task_helper::tz_parse(tz.as_deref().unwrap_or(""))?;
+                Ok(())
+            }
+            Trigger::Interval { secs: 0 } => Err("interval must be positive".to_string()),
+            Trigger::Interval { .. } => Ok(()),
+            Trigger::FileWatch { dir, pattern } => {
+                if !Path::new(dir).is_dir() {
+                    return Err(format!("directory not found: {}", dir));
+                }
+                if let Some(pattern) = pattern {
+                    Regex::new(pattern).map_err(|e| format!("invalid pattern: {}", e))?;
+                }
+                Ok(())
+            }
+            Trigger::Webhook { port: 0, .. } => Err("webhook port must be set".to_string()),
+            Trigger::Webhook { .. } => Ok(()),
+        }
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Trigger::Cron { expr, tz } => {
+                let schedule = task_helper::cron_parse(expr).ok()?;
+                let tz = task_helper::tz_parse(tz.as_deref().unwrap_or("")).ok()?;
+                task_helper::next_fire(&schedule, tz, after)
+            }
+            Trigger::Interval { secs } => Some(after + ChronoDuration::seconds(*secs as i64)),
+            // event triggers have no fire time of their own
+            Trigger::FileWatch { .. } | Trigger::Webhook { .. } => None,
+        }
+    }
+}
+
+/// What to do with fire times that passed while the app was closed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CatchUp {
+    #[default]
+    Skip,
+    RunOnce,
+    RunAll {
+        max: u32,
+    },
+}
+
+/// What to do when a job fires while its previous run is still going.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overlap {
+    #[default]
+    Skip,
+    Queue,
+    Parallel,
+    /// The previous run stops before its next node, or right away while it sits in
+    /// `sleep`, `timing` or `download_file`.
+    CancelPrevious,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub name: String,
+    pub script: String,
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    #[serde(default)]
+    pub overlap: Overlap,
+    pub paused: bool,
+    pub created_at: String,
+    pub last_run: Option<String>,
+    pub last_status: Option<String>,
+    pub next_run: Option<String>,
+}
+
+fn parse_time(raw: &Option<String>) -> Option<DateTime<Utc>> {
+    raw.as_ref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+impl Job {
+    fn schedule_from(&mut self, after: DateTime<Utc>) {
+        self.next_run = self.trigger.next_after(after).map(|t| t.to_rfc3339());
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        !self.paused && parse_time(&self.next_run).is_some_and(|t| t <= now)
+    }
+
+    // counts fire times between the last run (or creation) and now, capped by the policy
+    fn missed_runs(&self, now: DateTime<Utc>) -> u32 {
+        let limit = match self.catch_up {
+            _ if self.paused => return 0,
+            CatchUp::Skip => return 0,
+            CatchUp::RunOnce => 1,
+            CatchUp::RunAll { max } => max,
+        };
+        let mut from =
+            match parse_time(&self.last_run).or(parse_time(&Some(self.created_at.clone()))) {
+                Some(from) => from,
+                None => return 0,
+            };
+        let mut missed = 0;
+        while missed < limit {
+            match self.trigger.next_after(from) {
+                Some(t) if t <= now => {
+                    missed += 1;
+                    from = t;
+                }
+                _ => break,
+            }
+        }
+        missed
+    }
+}
+
+#[derive(Debug)]
+pub struct Scheduler {
+    path: PathBuf,
+    option: ExecuteOption,
+    jobs: Mutex<Vec<Job>>,
+    running: Mutex<HashMap<String, Vec<Arc<AtomicBool>>>>,
+    pending: Mutex<HashMap<String, VecDeque<Inputs>>>,
+    seen: Mutex<HashMap<String, HashSet<PathBuf>>>,
+}
+
+impl Scheduler {
+    /// Loads the registrations stored at `path`, a missing file starts empty.
+    /// A file that does not parse is kept as `<path>.bak` instead of being overwritten.
+    pub fn load(path: PathBuf, option: ExecuteOption) -> Self {
+        let mut jobs: Vec<Job> = match fs::read_to_string(&path) {
+            Ok(raw) => match serde_json::from_str(&raw) {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    let backup = path.with_extension("json.bak");
+                    println!("load schedules fail: {}, kept as {}", e, backup.display());
+                    if let Err(e) = fs::rename(&path, &backup) {
+                        println!("back up schedules fail: {}", e);
+                    }
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+
+        // missed runs are replayed by `start` according to each job's catch-up policy
+        let now = Utc::now();
+        for job in jobs.iter_mut() {
+            job.schedule_from(now);
+        }
+
+        let scheduler = Scheduler {
+            path,
+            option,
+            jobs: Mutex::new(jobs),
+            running: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            seen: Mutex::new(HashMap::new()),
+        };
+        // nothing to store for an empty list, which also leaves an unreadable file in place
+        // when it could not be moved aside
+        {
+            let jobs = scheduler.jobs.lock().unwrap();
+            if !jobs.is_empty() {
+                scheduler.persist(&jobs);
+            }
+        }
+        scheduler
+    }
+
+    fn persist(&self, jobs: &[Job]) {
+        let save = || -> Result<(), Box<dyn Error>> {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&self.path, serde_json::to_string_pretty(jobs)?)?;
+            Ok(())
+        };
+        if let Err(e) = save() {
+            println!("save schedules fail: {}", e);
+        }
+    }
+
+    // applies a change to one job and stores the result
+    fn update<F>(&self, id: &str, change: F) -> Result<Job, String>
+    where
+        F: FnOnce(&mut Job),
+    {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.id == id)
+            .ok_or(format!("schedule not found: {}", id))?;
+        change(job);
+        let job = job.clone();
+        self.persist(&jobs);
+        Ok(job)
+    }
+
+    // two jobs added within the same millisecond get a numbered suffix
+    fn new_id(jobs: &[Job], now: DateTime<Utc>) -> String {
+        let base = format!("job-{}", now.format("%Y%m%d%H%M%S%3f"));
+        let mut id = base.clone();
+        let mut n = 1;
+        while jobs.iter().any(|j| j.id == id) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        id
+    }
+
+    pub fn add(
+        self: &Arc<Self>,
+        name: &str,
+        script: &str,
+        trigger: Trigger,
+        catch_up: CatchUp,
+        overlap: Overlap,
+    ) -> Result<Job, String> {
+        trigger.validate()?;
+        application::check(script).map_err(|e| format!("invalid script: {}", e))?;
+
+        let now = Utc::now();
+        let mut job = Job {
+            id: String::new(),
+            name: name.to_string(),
+            script: script.to_string(),
+            trigger,
+            catch_up,
+            overlap,
+            paused: false,
+            created_at: now.to_rfc3339(),
+            last_run: None,
+            last_status: None,
+            next_run: None,
+        };
+        job.schedule_from(now);
+
+        {
+            // the id is picked under the same lock that stores the job
+            let mut jobs = self.jobs.lock().unwrap();
+            job.id = Scheduler::new_id(&jobs, now);
+            jobs.push(job.clone());
+        }
+        // a webhook whose port cannot be bound is refused instead of silently never firing
+        if let Err(e) = self.listen(&job) {
+            self.jobs.lock().unwrap().retain(|j| j.id != job.id);
+            return Err(e);
+        }
+        self.persist(&self.jobs.lock().unwrap());
+        Ok(job)
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    pub fn pause(&self, id: &str) -> Result<Job, String> {
+        self.update(id, |job| job.paused = true)
+    }
+
+    pub fn resume(&self, id: &str) -> Result<Job, String> {
+        self.update(id, |job| {
+            job.paused = false;
+            job.schedule_from(Utc::now());
+        })
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        self.pending.lock().unwrap().remove(id);
+        self.seen.lock().unwrap().remove(id);
+        let mut jobs = self.jobs.lock().unwrap();
+        let before = jobs.len();
+        jobs.retain(|j| j.id != id);
+        if jobs.len() == before {
+            return Err(format!("schedule not found: {}", id));
+        }
+        self.persist(&jobs);
+        Ok(())
+    }
+
+    /// Starts the job immediately, its regular schedule is left as it is.
+    pub fn run_now(self: &Arc<Self>, id: &str) -> Result<(), String> {
+        let job = self.find(id).ok_or(format!("schedule not found: {}", id))?;
+        if !self.launch(job, Inputs::new()) {
+            return Err(format!("schedule is already running: {}", id));
+        }
+        Ok(())
+    }
+
+    fn find(&self, id: &str) -> Option<Job> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|j| j.id == id)
+            .cloned()
+    }
+
+    // the listener lives as long as the job, a paused job answers with a conflict
+    fn listen(self: &Arc<Self>, job: &Job) -> Result<(), String> {
+        let (port, path) = match &job.trigger {
+            Trigger::Webhook { port, path } => (*port, path.clone().unwrap_or("/".to_string())),
+            _ => return Ok(()),
+        };
+        let alive = {
+            let (scheduler, id) = (Arc::downgrade(self), job.id.clone());
+            move || scheduler.upgrade().is_some_and(|s| s.find(&id).is_some())
+        };
+        let handle = {
+            let (scheduler, id) = (Arc::downgrade(self), job.id.clone());
+            move |inputs: Inputs| {
+                let scheduler = scheduler.upgrade().ok_or("scheduler stopped")?;
+                let job = scheduler.find(&id).ok_or("schedule removed")?;
+                if job.paused {
+                    return Err(format!("schedule is paused: {}", id));
+                }
+                match scheduler.launch(job, inputs) {
+                    true => Ok(format!("schedule {} started", id)),
+                    false => Err(format!("schedule is already running: {}", id)),
+                }
+            }
+        };
+        webhook::serve(port, &path, alive, handle)
+    }
+
+    fn catch_up(self: &Arc<Self>) {
+        let now = Utc::now();
+        for job in self.list() {
+            let missed = job.missed_runs(now);
+            if missed == 0 {
+                continue;
+            }
+            println!("schedule {} catches up {} missed run(s)", job.id, missed);
+            // missed runs always go one after another, whatever the overlap policy
+            if self.launch(job.clone(), Inputs::new()) {
+                let queue = (1..missed).map(|_| Inputs::new());
+                self.pending
+                    .lock()
+                    .unwrap()
+                    .entry(job.id)
+                    .or_default()
+                    .extend(queue);
+            }
+        }
+    }
+
+    // files already in the directory when watching starts never trigger a run
+    fn new_files(&self, job: &Job, dir: &str, pattern: &Option<String>) -> Vec<PathBuf> {
+        let current = task_helper::list_files(Path::new(dir));
+        let pattern = pattern.as_deref().and_then(|p| Regex::new(p).ok());
+        let mut seen = self.seen.lock().unwrap();
+        let known = match seen.get_mut(&job.id) {
+            Some(known) => known,
+            None => {
+                seen.insert(job.id.clone(), current);
+                return Vec::new();
+            }
+        };
+
+        let fresh = current
+            .difference(known)
+            .filter(|path| path.is_file() && !task_helper::is_partial(path))
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                pattern.as_ref().is_none_or(|re| re.is_match(name))
+            })
+            .cloned()
+            .collect();
+        *known = current;
+        fresh
+    }
+
+    fn tick(self: &Arc<Self>) {
+        let now = Utc::now();
+        let due: Vec<Job> = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let due: Vec<Job> = jobs
+                .iter_mut()
+                .filter(|j| j.is_due(now))
+                .map(|job| {
+                    job.schedule_from(now);
+                    job.clone()
+                })
+                .collect();
+            if !due.is_empty() {
+                self.persist(&jobs);
+            }
+            due
+        };
+
+        for job in due {
+            if !self.launch(job.clone(), Inputs::new()) {
+                println!("schedule {} still running, skip this run", job.id);
+            }
+        }
+
+        for job in self.list() {
+            let files = match &job.trigger {
+                Trigger::FileWatch { dir, pattern } => self.new_files(&job, dir, pattern),
+                _ => continue,
+            };
+            for file in files.into_iter().filter(|_| !job.paused) {
+                let inputs = Inputs::from([
+                    ("file_path".to_string(), file.to_string_lossy().to_string()),
+                    (
+                        "file_name".to_string(),
+                        file.file_name()
+                            .map_or(String::new(), |n| n.to_string_lossy().to_string()),
+                    ),
+                ]);
+                if !self.launch(job.clone(), inputs) {
+                    println!("schedule {} still running, skip {}", job.id, file.display());
+                }
+            }
+        }
+    }
+
+    // false means the run was dropped because the previous one is still going
+    fn launch(self: &Arc<Self>, job: Job, inputs: Inputs) -> bool {
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let mut running = self.running.lock().unwrap();
+            let active = running.entry(job.id.clone()).or_default();
+            if !active.is_empty() {
+                match job.overlap {
+                    Overlap::Skip => return false,
+                    Overlap::Queue => {
+                        let mut pending = self.pending.lock().unwrap();
+                        pending.entry(job.id).or_default().push_back(inputs);
+                        return true;
+                    }
+                    Overlap::Parallel => {}
+                    Overlap::CancelPrevious => {
+                        active.iter().for_each(|c| c.store(true, Ordering::SeqCst));
+                    }
+                }
+            }
+            active.push(Arc::clone(&cancel));
+        }
+        let _ = self.update(&job.id, |j| j.last_run = Some(Utc::now().to_rfc3339()));
+
+        let scheduler = Arc::clone(self);
+        let kind = match job.trigger {
+            Trigger::Cron { .. } | Trigger::Interval { .. } => "schedule",
+            Trigger::FileWatch { .. } => "file_watch",
+            Trigger::Webhook { .. } => "webhook",
+        };
+        let option = ExecuteOption {
+            cancel: Some(Arc::clone(&cancel)),
+            inputs,
+            meta: RunMeta {
+                trigger: format!("{}:{}", kind, job.id),
+                ..Default::default()
+            },
+            ..self.option.clone()
+        };
+        thread::spawn(move || {
+            let status = match application::app(&job.script, &option) {
+                Ok(report) => report.status,
+                Err(e) => format!("error: {}", e),
+            };
+            println!("schedule {} finished: {}", job.id, status);
+            let _ = scheduler.update(&job.id, |j| j.last_status = Some(status));
+            scheduler.finish(&job.id, &cancel);
+        });
+        true
+    }
+
+    // drops the finished run and starts the next queued one, if any
+    fn finish(self: &Arc<Self>, id: &str, cancel: &Arc<AtomicBool>) {
+        {
+            let mut running = self.running.lock().unwrap();
+            if let Some(active) = running.get_mut(id) {
+                active.retain(|c| !Arc::ptr_eq(c, cancel));
+                if !active.is_empty() {
+                    return;
+                }
+            }
+        }
+
+        let queued = self
+            .pending
+            .lock()
+            .unwrap()
+            .get_mut(id)
+            .and_then(|queue| queue.pop_front());
+        if let (Some(job), Some(inputs)) = (self.find(id), queued) {
+            self.launch(job, inputs);
+        }
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        for job in self.list() {
+            if let Err(e) = self.listen(&job) {
+                println!("schedule {} webhook fail: {}", job.id, e);
+            }
+        }
+        self.catch_up();
+        let scheduler = Arc::clone(self);
+        thread::spawn(move || loop {
+            scheduler.tick();
+            thread::sleep(Duration::from_secs(1));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = r#"
+        flowchart TD
+        a["name: head, type: control"]
+        b["name: end, type: control"]
+        a -->|success| b
+    "#;
+
+    fn script() -> String {
+        use base64::prelude::*;
+        BASE64_STANDARD.encode(FLOW)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mashu-scheduler-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn job(catch_up: CatchUp, overlap: Overlap) -> Job {
+        Job {
+            id: "job-1".to_string(),
+            name: "every minute".to_string(),
+            script: script(),
+            trigger: Trigger::Interval { secs: 60 },
+            catch_up,
+            overlap,
+            paused: false,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            last_run: None,
+            last_status: None,
+            next_run: None,
+        }
+    }
+
+    fn at(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    // a scheduler holding `job` with a run already marked as active
+    fn busy(name: &str, job: Job) -> (Arc<Scheduler>, Arc<AtomicBool>) {
+        let dir = temp_dir(name);
+        let option = ExecuteOption {
+            artifact_root: dir.join("runs"),
+            ..Default::default()
+        };
+        let scheduler = Arc::new(Scheduler::load(dir.join("schedules.json"), option));
+        scheduler.jobs.lock().unwrap().push(job);
+        let active = Arc::new(AtomicBool::new(false));
+        scheduler
+            .running
+            .lock()
+            .unwrap()
+            .insert("job-1".to_string(), vec![Arc::clone(&active)]);
+        (scheduler, active)
+    }
+
+    fn wait_finished(scheduler: &Scheduler) -> Job {
+        for _ in 0..100 {
+            let job = scheduler.find("job-1").unwrap();
+            if job.last_status.is_some() {
+                return job;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("the run did not finish");
+    }
+
+    #[test]
+    fn cron_describe_reads_times_steps_and_fields() {
+        assert_eq!(cron_describe("0 32 21 * * * *"), "at 21:32:00, every day");
+        assert_eq!(
+            cron_describe("0 0 9 * * Mon-Fri *"),
+            "at 09:00:00, day of week Mon-Fri"
+        );
+        assert_eq!(
+            cron_describe("0 */15 * * * * *"),
+            "second 0, minute every 15"
+        );
+        assert_eq!(cron_describe("* * * * * * *"), "every second");
+    }
+
+    #[test]
+    fn preview_cron_lists_fire_times_in_the_zone() {
+        let preview = preview_cron("0 32 21 * * * *", "Asia/Tokyo", 3).unwrap();
+        assert_eq!(preview.timezone, "Asia/Tokyo");
+        assert_eq!(preview.summary, "at 21:32:00, every day");
+        assert_eq!(preview.next.len(), 3);
+        let times: Vec<DateTime<Utc>> = preview
+            .next
+            .iter()
+            .map(|t| {
+                assert!(t.ends_with("T21:32:00+09:00"), "{}", t);
+                at(t)
+            })
+            .collect();
+        assert_eq!(times[1] - times[0], ChronoDuration::days(1));
+        assert!(times[0] > Utc::now());
+
+        assert_eq!(
+            preview_cron("0 0 * * * * *", "", 1).unwrap().timezone,
+            "local"
+        );
+        assert!(preview_cron("0 61 * * * * *", "", 3).is_err());
+        assert!(preview_cron("0 32 21 * * * *", "Mars/Olympus", 3).is_err());
+    }
+
+    #[test]
+    fn missed_runs_follow_the_catch_up_policy() {
+        let now = at("2026-01-01T00:10:30Z");
+        assert_eq!(job(CatchUp::Skip, Overlap::Skip).missed_runs(now), 0);
+        assert_eq!(job(CatchUp::RunOnce, Overlap::Skip).missed_runs(now), 1);
+        assert_eq!(
+            job(CatchUp::RunAll { max: 3 }, Overlap::Skip).missed_runs(now),
+            3
+        );
+        assert_eq!(
+            job(CatchUp::RunAll { max: 100 }, Overlap::Skip).missed_runs(now),
+            10
+        );
+
+        // counted from the last run when there is one
+        let mut recent = job(CatchUp::RunAll { max: 100 }, Overlap::Skip);
+        recent.last_run = Some("2026-01-01T00:08:00Z".to_string());
+        assert_eq!(recent.missed_runs(now), 2);
+
+        recent.paused = true;
+        assert_eq!(recent.missed_runs(now), 0);
+    }
+
+    #[test]
+    fn is_due_compares_the_next_run() {
+        let now = at("2026-01-01T00:10:00Z");
+        let mut due = job(CatchUp::Skip, Overlap::Skip);
+        assert!(!due.is_due(now));
+        due.next_run = Some("2026-01-01T00:09:59Z".to_string());
+        assert!(due.is_due(now));
+        due.next_run = Some("2026-01-01T00:10:01Z".to_string());
+        assert!(!due.is_due(now));
+        due.next_run = Some("2026-01-01T00:09:59Z".to_string());
+        due.paused = true;
+        assert!(!due.is_due(now));
+    }
+
+    #[test]
+    fn overlap_skip_drops_the_new_run() {
+        let (scheduler, active) = busy("skip", job(CatchUp::Skip, Overlap::Skip));
+        assert!(!scheduler.launch(scheduler.find("job-1").unwrap(), Inputs::new()));
+        assert!(!active.load(Ordering::SeqCst));
+        assert!(scheduler.pending.lock().unwrap().is_empty());
+        assert!(scheduler.find("job-1").unwrap().last_run.is_none());
+    }
+
+    #[test]
+    fn overlap_queue_runs_after_the_active_one() {
+        let (scheduler, active) = busy("queue", job(CatchUp::Skip, Overlap::Queue));
+        let inputs = Inputs::from([("user".to_string(), "alice".to_string())]);
+        assert!(scheduler.launch(scheduler.find("job-1").unwrap(), inputs));
+        assert_eq!(scheduler.pending.lock().unwrap()["job-1"].len(), 1);
+        assert!(scheduler.find("job-1").unwrap().last_run.is_none());
+
+        scheduler.finish("job-1", &active);
+        assert!(scheduler.pending.lock().unwrap()["job-1"].is_empty());
+        assert!(wait_finished(&scheduler).last_run.is_some());
+    }
+
+    #[test]
+    fn overlap_cancel_previous_stops_the_active_run() {
+        let (scheduler, active) = busy("cancel", job(CatchUp::Skip, Overlap::CancelPrevious));
+        assert!(scheduler.launch(scheduler.find("job-1").unwrap(), Inputs::new()));
+        assert!(active.load(Ordering::SeqCst));
+        wait_finished(&scheduler);
+    }
+
+    #[test]
+    fn unreadable_file_is_kept_aside() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join("schedules.json");
+        fs::write(&path, "[{\"id\": \"job-1\"").unwrap();
+
+        let scheduler = Scheduler::load(path.clone(), ExecuteOption::default());
+        assert!(scheduler.list().is_empty());
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("schedules.json.bak")).unwrap(),
+            "[{\"id\": \"job-1\""
+        );
+    }
+
+    #[test]
+    fn ids_in_the_same_millisecond_get_a_suffix() {
+        let dir = temp_dir("ids");
+        let scheduler = Arc::new(Scheduler::load(
+            dir.join("schedules.json"),
+            ExecuteOption::default(),
+        ));
+        let first = scheduler
+            .add(
+                "a",
+                &script(),
+                Trigger::Interval { secs: 60 },
+                CatchUp::Skip,
+                Overlap::Skip,
+            )
+            .unwrap();
+        let now = DateTime::parse_from_rfc3339(&first.created_at)
+            .unwrap()
+            .with_timezone(&Utc);
+        let jobs = scheduler.list();
+        assert_ne!(Scheduler::new_id(&jobs, now), first.id);
+        assert_eq!(Scheduler::new_id(&jobs, now), format!("{}-1", first.id));
+    }
+}
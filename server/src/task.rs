@@ -1,13 +1,27 @@
 use chrono::Utc;
+use chrono_tz::Tz;
 use cron::Schedule;
-use std::collections::HashMap;
-use std::str::FromStr;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 use std::{thread, time::Duration};
+use thirtyfour::components::SelectElement;
 use thirtyfour::error::WebDriverErrorInfo;
 use thirtyfour::prelude::*;
 use tokio::runtime::Runtime;
 
-use crate::task_helper;
+use crate::driver::DriverProcess;
+use crate::misc::{self, HttpRequest};
+use crate::pool::{PooledSession, ResetPolicy, SessionPool};
+use crate::profile::{self, Profile};
+use crate::report::CheckReport;
+use crate::task_helper::{self, FieldSpec, Row};
 
 #[derive(Debug)]
 pub enum ExecutionResult {
@@ -22,6 +36,21 @@ pub struct Workspace {
     pub variables: HashMap<String, String>,
     pub execution_log: Vec<String>,
     pub web_driver: Option<WebDriver>,
+    pub driver_process: Option<DriverProcess>,
+    pub web_option: WebOption,
+    pub pool: Option<Arc<SessionPool>>,
+    pub session_lease: Option<(String, ResetPolicy)>,
+    pub origins: Vec<String>,
+    pub window: Option<WindowHandle>,
+    pub frames: Vec<String>,
+    pub artifact_dir: PathBuf,
+    pub artifacts: Vec<String>,
+    pub profile_dir: PathBuf,
+    pub checks: Vec<CheckReport>,
+    pub datasets: HashMap<String, Vec<Row>>,
+    pub timezone: Option<Tz>,
+    pub remote_url: Option<String>,
+    pub cancel: Option<Arc<AtomicBool>>,
 }
 
 impl Workspace {
@@ -40,15 +69,185 @@ impl Workspace {
         self.variables.get(key)
     }
 
+    // replaces `${name}` with the variable value, unknown names are left untouched
+    pub fn expand(&self, text: &str) -> String {
+        self.substitute(text, |value| value.to_string())
+    }
+
+    // same for a node's raw parameters, where a value sits inside quotes and its own
+    // quotes are written the way mermaid does
+    pub fn expand_param(&self, para: &str) -> String {
+        self.substitute(para, |value| {
+            value.replace('"', "#quot;").replace('\'', "#39;")
+        })
+    }
+
+    fn substitute<F: Fn(&str) -> String>(&self, text: &str, escape: F) -> String {
+        let re = Regex::new(r"\$\{([\w.-]+)\}").expect("invalid variable pattern");
+        re.replace_all(text, |cap: &regex::Captures| {
+            self.get_variable(&cap[1])
+                .map_or(cap[0].to_string(), |value| escape(value))
+        })
+        .to_string()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::SeqCst))
+    }
+
+    // sleeps in short slices so a cancelled run stops waiting, false once cancelled
+    pub fn wait(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        while !self.is_cancelled() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return true;
+            }
+            thread::sleep(left.min(Duration::from_millis(200)));
+        }
+        false
+    }
+
     pub fn set_web_driver(&mut self, driver: WebDriver) -> bool {
         self.web_driver = Some(driver);
         self.web_driver.is_some()
     }
 
+    /// Opens a fresh browser, on `url` when given or on a driver spawned for it.
+    pub fn start_web_driver(
+        &mut self,
+        url: Option<&str>,
+        mut option: WebOption,
+    ) -> Result<(), String> {
+        // downloads land in the run's own artifact directory unless init_web names one
+        let dir = match &option.download_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => self.artifact_dir.join("downloads"),
+        };
+        let dir = task_helper::download_dir(&dir);
+        option.download_dir = Some(dir.to_string_lossy().to_string());
+        let caps = task_helper::build_capabilities(&option)
+            .map_err(|e| format!("invalid browser option: {}", e))?;
+
+        // without an explicit url the server starts its own driver on a free port
+        let driver_url = match url {
+            Some(url) => url.to_string(),
+            None => {
+                let process = DriverProcess::spawn(&option.browser, option.driver_path.as_deref())
+                    .map_err(|e| format!("start driver fail: {}", e))?;
+                let driver_url = process.url.clone();
+                self.driver_process = Some(process);
+                driver_url
+            }
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let driver = rt
+            .block_on(async { WebDriver::new(&driver_url, caps).await })
+            .map_err(|e| e.to_string())?;
+        self.set_web_driver(driver);
+        self.web_option = option;
+        self.remote_url = url.map(String::from);
+        Ok(())
+    }
+
     pub fn get_web_driver(&self) -> Option<&WebDriver> {
         self.web_driver.as_ref()
     }
 
+    // leased sessions go back to the pool, owned ones are quit together with their driver
+    pub fn release_web_driver(&mut self) {
+        let driver = match self.web_driver.take() {
+            Some(driver) => driver,
+            None => return,
+        };
+        let process = self.driver_process.take();
+        let in_frame = !self.frames.is_empty();
+        self.set_window(None);
+
+        match (self.session_lease.take(), &self.pool) {
+            (Some((tag, reset)), Some(pool)) => {
+                // the next run starts on the top document of the tab this one ended on
+                if in_frame {
+                    let rt = Runtime::new().expect("create runtime fail");
+                    let _ = rt.block_on(driver.enter_default_frame());
+                }
+                let option = self.web_option.clone();
+                let origins = std::mem::take(&mut self.origins);
+                let session = PooledSession::new(&tag, driver, process, option, origins);
+                pool.give_back(session, reset);
+            }
+            _ => {
+                let rt = Runtime::new().expect("create runtime fail");
+                let _ = rt.block_on(async { driver.quit().await });
+            }
+        }
+    }
+
+    // remembered for a pooled session, so its reset knows which origins to clear
+    pub fn note_origin(&mut self, url: &str) {
+        let origin = match reqwest::Url::parse(url) {
+            Ok(url) if url.origin().is_tuple() => url.origin().ascii_serialization(),
+            _ => return,
+        };
+        if !self.origins.contains(&origin) {
+            self.origins.push(origin);
+        }
+    }
+
+    pub fn set_window(&mut self, handle: Option<WindowHandle>) {
+        self.window = handle;
+        self.frames.clear();
+    }
+
+    pub fn add_artifact(&mut self, path: &Path) {
+        self.artifacts.push(path.to_string_lossy().to_string());
+    }
+
+    pub fn check(&mut self, passed: bool, message: &str) {
+        self.log(&format!(
+            "check {}: {}",
+            if passed { "passed" } else { "failed" },
+            message
+        ));
+        self.checks.push(CheckReport {
+            passed,
+            message: message.to_string(),
+            ..Default::default()
+        });
+    }
+
+    // rows either extend a named dataset, kept as json next to the report,
+    // or land in variables as `<result>.<field>` from the first row
+    pub fn store_rows(
+        &mut self,
+        rows: Vec<Row>,
+        result: &str,
+        dataset: Option<&str>,
+    ) -> Result<(), String> {
+        self.set_variable(&format!("{}.count", result), &rows.len().to_string());
+        if let Some(name) = dataset {
+            if !misc::is_safe_name(name) {
+                return Err(format!("invalid dataset name: {}", name));
+            }
+            let data = self.datasets.entry(name.to_string()).or_default();
+            data.extend(rows);
+            let content = serde_json::to_string_pretty(data).unwrap_or_default();
+            let path = self.artifact_dir.join(format!("{}.json", name));
+            fs::write(&path, content).map_err(|e| format!("write dataset fail: {}", e))?;
+            self.add_artifact(&path);
+            return Ok(());
+        }
+        if let Some(row) = rows.first() {
+            for (field, value) in row {
+                self.set_variable(&format!("{}.{}", result, field), value);
+            }
+        }
+        Ok(())
+    }
+
     pub fn log(&mut self, message: &str) {
         self.execution_log.push(message.to_string());
         println!("[{}] {}", self.id, message);
@@ -97,12 +296,14 @@ impl ControlTask {
 
 pub struct HeadTack {
     base: ControlTask,
+    tz: Option<Tz>,
 }
 
 impl HeadTack {
-    pub fn new() -> Self {
+    pub fn new(tz: Option<Tz>) -> Self {
         HeadTack {
             base: ControlTask::new("head"),
+            tz,
         }
     }
 }
@@ -110,6 +311,8 @@ impl HeadTack {
 impl Task for HeadTack {
     fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
         ws.log(&format!("run head"));
+        // the head's timezone is the workflow default for timing nodes
+        ws.timezone = self.tz;
         ExecutionResult::Success
     }
 }
@@ -130,11 +333,7 @@ impl Task for EndTack {
     fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
         ws.log("run end: cleaning workspace");
 
-        if let Some(driver) = ws.web_driver.take() {
-            let run = Runtime::new().expect("create runtime fail");
-            let _ = run.block_on(async { driver.quit().await });
-        }
-        ws.web_driver = None;
+        ws.release_web_driver();
         ws.variables.clear();
         ws.execution_log.clear();
         ExecutionResult::Success
@@ -158,37 +357,37 @@ impl SleepTack {
 impl Task for SleepTack {
     fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
         ws.log(&format!("run sleep"));
-        thread::sleep(Duration::from_millis(self.time));
+        if !ws.wait(Duration::from_millis(self.time)) {
+            ws.log("sleep cancelled");
+            return ExecutionResult::Failure;
+        }
         ExecutionResult::Success
     }
 }
 
 pub struct TimingTack {
     base: ControlTask,
-    cron: String,
+    schedule: Schedule,
+    tz: Option<Tz>,
 }
 
 impl TimingTack {
-    pub fn new(cron: Option<&str>) -> Self {
+    pub fn new(schedule: Schedule, tz: Option<Tz>) -> Self {
         TimingTack {
             base: ControlTask::new("timing"),
-            cron: cron.unwrap_or("* * * * * * *").to_string(),
+            schedule,
+            tz,
         }
     }
 }
 
 impl Task for TimingTack {
     fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
-        ws.log(&format!("run timing: {}", self.cron));
-        let schedule = match Schedule::from_str(&self.cron) {
-            Ok(s) => s,
-            Err(_) => {
-                ws.log(&format!("invalid cron expression"));
-                return ExecutionResult::Failure;
-            }
-        };
+        let tz = self.tz.or(ws.timezone);
+        let zone = tz.map_or("local".to_string(), |tz| tz.to_string());
+        ws.log(&format!("run timing: {} ({})", self.schedule, zone));
 
-        let next_trigger_time = match schedule.upcoming(Utc).next() {
+        let next_trigger_time = match task_helper::next_fire(&self.schedule, tz, Utc::now()) {
             Some(time) => time,
             None => {
                 ws.log("no next trigger time found");
@@ -196,13 +395,136 @@ impl Task for TimingTack {
             }
         };
 
-        let duration = next_trigger_time - Utc::now();
-        let offset_duration = duration.num_seconds().saturating_sub(8 * 60 * 60);
-        thread::sleep(Duration::from_secs(offset_duration as u64));
+        ws.log(&format!(
+            "next trigger time: {}",
+            next_trigger_time.to_rfc3339()
+        ));
+        let duration = (next_trigger_time - Utc::now())
+            .to_std()
+            .unwrap_or_default();
+        if !ws.wait(duration) {
+            ws.log("timing cancelled");
+            return ExecutionResult::Failure;
+        }
         ExecutionResult::Success
     }
 }
 
+pub struct AssertTack {
+    base: ControlTask,
+    target: String,
+    component: String,
+    attribute: String,
+    variable: String,
+    expect: String,
+    mode: String,
+}
+
+impl AssertTack {
+    pub fn new(
+        target: Option<&str>,
+        comp: Option<&str>,
+        attribute: Option<&str>,
+        variable: Option<&str>,
+        expect: Option<&str>,
+        mode: Option<&str>,
+    ) -> Self {
+        AssertTack {
+            base: ControlTask::new("assert"),
+            target: target.unwrap_or("present").to_string(),
+            component: comp.unwrap_or("").to_string(),
+            attribute: attribute.unwrap_or("").to_string(),
+            variable: variable.unwrap_or("").to_string(),
+            expect: expect.unwrap_or("").to_string(),
+            mode: mode.unwrap_or("equals").to_string(),
+        }
+    }
+
+    fn compare(&self, actual: &str) -> Result<bool, String> {
+        match self.mode.as_str() {
+            "equals" => Ok(actual == self.expect),
+            "contains" => Ok(actual.contains(&self.expect)),
+            "regex" => Regex::new(&self.expect)
+                .map(|re| re.is_match(actual))
+                .map_err(|_| format!("invalid regex: {}", self.expect)),
+            other => Err(format!("unknown match mode: {}", other)),
+        }
+    }
+
+    // reads the value under test, `None` means the element (or variable) is missing
+    fn actual(&self, ws: &Workspace) -> Result<Option<String>, String> {
+        if self.target == "variable" {
+            return Ok(ws.get_variable(&self.variable).cloned());
+        }
+        let driver = ws.get_web_driver().ok_or("no web driver".to_string())?;
+
+        let rt = Runtime::new().expect("create runtime fail");
+        rt.block_on(async {
+            let elem = match self.target.as_str() {
+                "url" => return Ok(Some(driver.current_url().await?.to_string())),
+                "title" => return Ok(Some(driver.title().await?)),
+                _ => match task_helper::find_component(driver, &self.component).await {
+                    Ok(elem) => elem,
+                    Err(_) => return Ok(None),
+                },
+            };
+            match self.target.as_str() {
+                "text" => Ok(Some(elem.text().await?)),
+                "attribute" => Ok(elem.attr(&self.attribute).await?),
+                _ => Ok(Some(String::new())),
+            }
+        })
+        .map_err(|e: WebDriverError| e.to_string())
+    }
+}
+
+impl Task for AssertTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run assert: {}", self.target));
+        let actual = match self.actual(ws) {
+            Ok(actual) => actual,
+            Err(e) => {
+                ws.check(false, &format!("{} could not be read: {}", self.target, e));
+                return ExecutionResult::Failure;
+            }
+        };
+
+        let subject = match self.target.as_str() {
+            "variable" => format!("variable {}", self.variable),
+            "url" | "title" => self.target.clone(),
+            _ => format!("{} of {}", self.target, self.component),
+        };
+        let (passed, message) = match (self.target.as_str(), actual) {
+            ("present", found) => (
+                found.is_some(),
+                format!("expected {} to be present", self.component),
+            ),
+            ("absent", found) => (
+                found.is_none(),
+                format!("expected {} to be absent", self.component),
+            ),
+            (_, None) => (false, format!("{} not found", subject)),
+            (_, Some(actual)) => match self.compare(&actual) {
+                Ok(passed) => (
+                    passed,
+                    format!(
+                        "expected {} to {} '{}', got '{}'",
+                        subject, self.mode, self.expect, actual
+                    ),
+                ),
+                Err(e) => (false, e),
+            },
+        };
+
+        ws.check(passed, &message);
+        if passed {
+            ExecutionResult::Success
+        } else {
+            ExecutionResult::Failure
+        }
+    }
+}
+
 pub struct OperateTask {
     base: BaseTask,
 }
@@ -215,31 +537,121 @@ impl OperateTask {
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebOption {
+    pub browser: String,
+    pub headless: bool,
+    pub window_size: Option<(u32, u32)>,
+    pub user_agent: Option<String>,
+    pub proxy: Option<String>,
+    pub insecure: bool,
+    pub download_dir: Option<String>,
+    pub driver_path: Option<String>,
+    pub unhandled_alert: Option<String>,
+    pub args: Vec<String>,
+    pub prefs: Vec<(String, String)>,
+}
+
 pub struct InitWebTack {
     base: OperateTask,
-    url: String,
+    url: Option<String>,
+    option: WebOption,
+    session: Option<String>,
+    reset: ResetPolicy,
 }
 
 impl InitWebTack {
-    pub fn new(url: Option<&str>) -> Self {
+    pub fn new(
+        url: Option<&str>,
+        option: WebOption,
+        session: Option<&str>,
+        reset: Option<&str>,
+    ) -> Self {
         InitWebTack {
             base: OperateTask::new("init_web"),
-            url: url.unwrap_or("http://localhost:9515").to_string(),
+            url: url.map(String::from),
+            option,
+            session: session.map(String::from),
+            reset: ResetPolicy::parse(reset.unwrap_or("keep")),
+        }
+    }
+
+    // runs without a tag share the untagged sessions
+    fn session_tag(&self) -> String {
+        self.session.clone().unwrap_or_default()
+    }
+
+    fn reuse_session(&self, ws: &mut Workspace) -> bool {
+        let tag = self.session_tag();
+        let pool = match &ws.pool {
+            Some(pool) => Arc::clone(pool),
+            None => return false,
+        };
+        let session = match pool.borrow(&tag, &self.option.browser) {
+            Some(session) => session,
+            None => return false,
+        };
+
+        // a browser closed by hand leaves a dead session behind, drop it and start fresh
+        let rt = Runtime::new().expect("create runtime fail");
+        let window = match rt.block_on(session.driver.window()) {
+            Ok(window) => window,
+            Err(_) => {
+                ws.log(&format!("pooled session {} is gone", tag));
+                return false;
+            }
+        };
+
+        // the pooled browser still downloads wherever its first run pointed it, which is
+        // usually that run's artifact directory, so it is moved or downloads are refused
+        let mut option = session.option;
+        let dir = match &self.option.download_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => ws.artifact_dir.join("downloads"),
+        };
+        let dir = task_helper::download_dir(&dir);
+        if option.download_dir.as_deref().map(PathBuf::from) != Some(dir.clone()) {
+            let moved = rt.block_on(task_helper::set_download_dir(
+                &session.driver,
+                &option.browser,
+                &dir,
+            ));
+            option.download_dir = match moved {
+                Ok(_) => Some(dir.to_string_lossy().to_string()),
+                Err(e) => {
+                    ws.log(&format!("pooled session can't download here: {}", e));
+                    None
+                }
+            };
         }
+
+        ws.log(&format!("reuse pooled session: {}", tag));
+        ws.web_driver = Some(session.driver);
+        ws.set_window(Some(window));
+        ws.driver_process = session.process;
+        ws.web_option = option;
+        ws.origins = session.origins;
+        ws.session_lease = Some((tag, self.reset));
+        true
     }
 }
 
 impl Task for InitWebTack {
     fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
-        // let caps = DesiredCapabilities::chrome();
-        let caps = DesiredCapabilities::firefox();
-        let rt = Runtime::new().expect("create runtime fail");
-        match rt.block_on(async { WebDriver::new(&self.url, caps).await }) {
-            Ok(driver) => {
-                ws.set_web_driver(driver);
+        ws.log(&format!("run init web: {}", self.option.browser));
+        if self.reuse_session(ws) {
+            return ExecutionResult::Success;
+        }
+
+        match ws.start_web_driver(self.url.as_deref(), self.option.clone()) {
+            Ok(_) => {
+                ws.session_lease = Some((self.session_tag(), self.reset));
                 ExecutionResult::Success
             }
-            Err(_) => ExecutionResult::Failure,
+            Err(e) => {
+                ws.log(&format!("init web fail: {}", e));
+                ExecutionResult::Failure
+            }
         }
     }
 }
@@ -268,10 +680,14 @@ impl Task for OpenWebTack {
             None => return ExecutionResult::Failure,
         };
 
+        // keep the window size requested by init_web instead of maximizing over it
+        let maximize = ws.web_option.window_size.is_none();
         let rt = Runtime::new().expect("create runtime fail");
         match rt.block_on(async {
             driver.goto(&self.url).await?;
-            driver.maximize_window().await?;
+            if maximize {
+                driver.maximize_window().await?;
+            }
             Ok::<(), WebDriverError>(())
         }) {
             Ok(_) => ExecutionResult::Success,
@@ -396,6 +812,1284 @@ impl Task for SummitTack {
     }
 }
 
+pub struct SelectOptionTack {
+    base: OperateTask,
+    component: String,
+    by: String,
+    option: String,
+}
+
+impl SelectOptionTack {
+    pub fn new(comp: Option<&str>, by: Option<&str>, option: Option<&str>) -> Self {
+        SelectOptionTack {
+            base: OperateTask::new("select_option"),
+            component: comp.unwrap_or("").to_string(),
+            by: by.unwrap_or("text").to_string(),
+            option: option.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for SelectOptionTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run select option");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let elem = task_helper::find_component(driver, &self.component).await?;
+            let select = SelectElement::new(&elem).await?;
+            match self.by.as_str() {
+                "value" => select.select_by_value(&self.option).await?,
+                "index" => {
+                    let index = self.option.parse::<u32>().map_err(|_| {
+                        WebDriverError::ParseError(format!("invalid index: {}", self.option))
+                    })?;
+                    select.select_by_index(index).await?
+                }
+                _ => select.select_by_visible_text(&self.option).await?,
+            }
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("select option fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct HoverTack {
+    base: OperateTask,
+    component: String,
+}
+
+impl HoverTack {
+    pub fn new(comp: Option<&str>) -> Self {
+        HoverTack {
+            base: OperateTask::new("hover"),
+            component: comp.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for HoverTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run hover");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let elem = task_helper::find_component(driver, &self.component).await?;
+            driver
+                .action_chain()
+                .move_to_element_center(&elem)
+                .perform()
+                .await?;
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("hover fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct ScrollIntoViewTack {
+    base: OperateTask,
+    component: String,
+}
+
+impl ScrollIntoViewTack {
+    pub fn new(comp: Option<&str>) -> Self {
+        ScrollIntoViewTack {
+            base: OperateTask::new("scroll_into_view"),
+            component: comp.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for ScrollIntoViewTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run scroll into view");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let elem = task_helper::find_component(driver, &self.component).await?;
+            elem.scroll_into_view().await?;
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("scroll into view fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct ScrollByTack {
+    base: OperateTask,
+    x: i64,
+    y: i64,
+}
+
+impl ScrollByTack {
+    pub fn new(x: Option<i64>, y: Option<i64>) -> Self {
+        ScrollByTack {
+            base: OperateTask::new("scroll_by"),
+            x: x.unwrap_or(0),
+            y: y.unwrap_or(0),
+        }
+    }
+}
+
+impl Task for ScrollByTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run scroll by: {}, {}", self.x, self.y));
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let script = "window.scrollBy(arguments[0], arguments[1]);";
+        match rt.block_on(driver.execute(script, vec![json!(self.x), json!(self.y)])) {
+            Ok(_) => ExecutionResult::Success,
+            Err(e) => {
+                ws.log(&format!("scroll by fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct ClearTack {
+    base: OperateTask,
+    component: String,
+}
+
+impl ClearTack {
+    pub fn new(comp: Option<&str>) -> Self {
+        ClearTack {
+            base: OperateTask::new("clear"),
+            component: comp.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for ClearTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run clear");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let elem = task_helper::find_component(driver, &self.component).await?;
+            elem.clear().await?;
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("clear fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct DoubleClickTack {
+    base: OperateTask,
+    component: String,
+}
+
+impl DoubleClickTack {
+    pub fn new(comp: Option<&str>) -> Self {
+        DoubleClickTack {
+            base: OperateTask::new("double_click"),
+            component: comp.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for DoubleClickTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run double click");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let elem = task_helper::find_component(driver, &self.component).await?;
+            driver
+                .action_chain()
+                .double_click_element(&elem)
+                .perform()
+                .await?;
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("double click fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct RightClickTack {
+    base: OperateTask,
+    component: String,
+}
+
+impl RightClickTack {
+    pub fn new(comp: Option<&str>) -> Self {
+        RightClickTack {
+            base: OperateTask::new("right_click"),
+            component: comp.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for RightClickTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run right click");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let elem = task_helper::find_component(driver, &self.component).await?;
+            driver
+                .action_chain()
+                .context_click_element(&elem)
+                .perform()
+                .await?;
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("right click fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct SendKeysTack {
+    base: OperateTask,
+    component: String,
+    keys: String,
+}
+
+impl SendKeysTack {
+    pub fn new(comp: Option<&str>, keys: Option<&str>) -> Self {
+        SendKeysTack {
+            base: OperateTask::new("send_keys"),
+            component: comp.unwrap_or("").to_string(),
+            keys: keys.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for SendKeysTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run send keys");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            // without a component the keys go to whatever element has focus
+            if !self.component.is_empty() {
+                let elem = task_helper::find_component(driver, &self.component).await?;
+                elem.focus().await?;
+            }
+            for chord in self.keys.split_whitespace() {
+                task_helper::press_chord(driver, chord).await?;
+            }
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("send keys fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct SwitchFrameTack {
+    base: OperateTask,
+    component: String,
+    index: Option<u16>,
+    to: String,
+}
+
+impl SwitchFrameTack {
+    pub fn new(comp: Option<&str>, index: Option<u16>, to: Option<&str>) -> Self {
+        SwitchFrameTack {
+            base: OperateTask::new("switch_frame"),
+            component: comp.unwrap_or("").to_string(),
+            index,
+            to: to.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for SwitchFrameTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run switch frame");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        // `to` leaves frames, a component or index enters one, nothing at all goes back to the top
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            match (self.to.as_str(), self.component.as_str(), self.index) {
+                ("parent", _, _) => driver.enter_parent_frame().await?,
+                ("top", _, _) | ("", "", None) => driver.enter_default_frame().await?,
+                (_, "", Some(index)) => driver.enter_frame(index).await?,
+                _ => {
+                    let frame = task_helper::find_component(driver, &self.component).await?;
+                    frame.enter_frame().await?
+                }
+            }
+            Ok::<(), WebDriverError>(())
+        });
+
+        if let Err(e) = ret {
+            ws.log(&format!("switch frame fail: {}", e));
+            return ExecutionResult::Failure;
+        }
+        match (self.to.as_str(), self.component.as_str(), self.index) {
+            ("parent", _, _) => {
+                ws.frames.pop();
+            }
+            ("top", _, _) | ("", "", None) => ws.frames.clear(),
+            (_, "", Some(index)) => ws.frames.push(format!("index:{}", index)),
+            _ => ws.frames.push(self.component.clone()),
+        }
+        ExecutionResult::Success
+    }
+}
+
+pub struct NewTabTack {
+    base: OperateTask,
+    url: String,
+}
+
+impl NewTabTack {
+    pub fn new(url: Option<&str>) -> Self {
+        NewTabTack {
+            base: OperateTask::new("new_tab"),
+            url: url.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for NewTabTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run new tab");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            let handle = driver.new_tab().await?;
+            driver.switch_to_window(handle.clone()).await?;
+            if !self.url.is_empty() {
+                driver.goto(&self.url).await?;
+            }
+            Ok::<WindowHandle, WebDriverError>(handle)
+        });
+
+        match ret {
+            Ok(handle) => {
+                ws.set_window(Some(handle));
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("new tab fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct SwitchTabTack {
+    base: OperateTask,
+    index: Option<usize>,
+    title: String,
+    url: String,
+}
+
+impl SwitchTabTack {
+    pub fn new(index: Option<usize>, title: Option<&str>, url: Option<&str>) -> Self {
+        SwitchTabTack {
+            base: OperateTask::new("switch_tab"),
+            index,
+            title: title.unwrap_or("").to_string(),
+            url: url.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for SwitchTabTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run switch tab");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+        let url_pattern = match Regex::new(&self.url) {
+            Ok(re) => re,
+            Err(_) => {
+                ws.log(&format!("invalid url pattern: {}", self.url));
+                return ExecutionResult::Failure;
+            }
+        };
+
+        // tabs are matched by index first, otherwise by title and url in handle order
+        let current = ws.window.clone();
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            let origin = match current {
+                Some(handle) => handle,
+                None => driver.window().await?,
+            };
+            let handles = driver.windows().await?;
+            if let Some(index) = self.index {
+                let handle = handles.get(index).cloned();
+                if let Some(handle) = &handle {
+                    driver.switch_to_window(handle.clone()).await?;
+                }
+                return Ok(handle);
+            }
+
+            for handle in handles {
+                driver.switch_to_window(handle.clone()).await?;
+                let title = driver.title().await?;
+                let url = driver.current_url().await?;
+                if title.contains(&self.title) && url_pattern.is_match(url.as_str()) {
+                    return Ok(Some(handle));
+                }
+            }
+            driver.switch_to_window(origin).await?;
+            Ok::<Option<WindowHandle>, WebDriverError>(None)
+        });
+
+        match ret {
+            Ok(Some(handle)) => {
+                ws.set_window(Some(handle));
+                ExecutionResult::Success
+            }
+            Ok(None) => {
+                ws.log("no tab matched");
+                ExecutionResult::Failure
+            }
+            Err(e) => {
+                ws.log(&format!("switch tab fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct CloseTabTack {
+    base: OperateTask,
+}
+
+impl CloseTabTack {
+    pub fn new() -> Self {
+        CloseTabTack {
+            base: OperateTask::new("close_tab"),
+        }
+    }
+}
+
+impl Task for CloseTabTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run close tab");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        // the session is left on the most recently opened tab that is still there
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            driver.close_window().await?;
+            let handle = driver.windows().await?.pop();
+            if let Some(handle) = &handle {
+                driver.switch_to_window(handle.clone()).await?;
+            }
+            Ok::<Option<WindowHandle>, WebDriverError>(handle)
+        });
+
+        match ret {
+            Ok(handle) => {
+                ws.set_window(handle);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("close tab fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct RunScriptTack {
+    base: OperateTask,
+    script: String,
+    is_async: bool,
+    args: Vec<String>,
+    result: String,
+}
+
+impl RunScriptTack {
+    pub fn new(
+        script: Option<&str>,
+        is_async: bool,
+        args: Option<&str>,
+        result: Option<&str>,
+    ) -> Self {
+        RunScriptTack {
+            base: OperateTask::new("run_script"),
+            script: script.unwrap_or("").to_string(),
+            is_async,
+            args: args
+                .map(|v| v.split(',').map(|a| a.trim().to_string()).collect())
+                .unwrap_or_default(),
+            result: result.unwrap_or("script_result").to_string(),
+        }
+    }
+}
+
+impl Task for RunScriptTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run script");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            let args = task_helper::script_args(driver, &ws.variables, &self.args).await?;
+            let ret = if self.is_async {
+                driver.execute_async(&self.script, args).await?
+            } else {
+                driver.execute(&self.script, args).await?
+            };
+            Ok::<Value, WebDriverError>(ret.json().clone())
+        });
+
+        match ret {
+            Ok(value) => {
+                ws.set_variable(&self.result, &task_helper::value_text(&value));
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("run script fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct UploadFileTack {
+    base: OperateTask,
+    component: String,
+    path: String,
+}
+
+impl UploadFileTack {
+    pub fn new(comp: Option<&str>, path: Option<&str>) -> Self {
+        UploadFileTack {
+            base: OperateTask::new("upload_file"),
+            component: comp.unwrap_or("").to_string(),
+            path: path.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for UploadFileTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run upload file: {}", self.path));
+        // file inputs only accept absolute paths of files that exist
+        let path = match fs::canonicalize(&self.path) {
+            Ok(path) if path.is_file() => path,
+            _ => {
+                ws.log(&format!("upload file not found: {}", self.path));
+                return ExecutionResult::Failure;
+            }
+        };
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            let input = task_helper::find_component(driver, &self.component).await?;
+            input.send_keys(path.to_string_lossy().to_string()).await?;
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.set_variable("last_option_component", &self.component);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("upload file fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct DownloadFileTack {
+    base: OperateTask,
+    component: String,
+    timeout: u64,
+    variable: String,
+}
+
+impl DownloadFileTack {
+    pub fn new(comp: Option<&str>, timeout: Option<u64>, variable: Option<&str>) -> Self {
+        DownloadFileTack {
+            base: OperateTask::new("download_file"),
+            component: comp.unwrap_or("").to_string(),
+            timeout: timeout.unwrap_or(30000u64),
+            variable: variable.unwrap_or("download_path").to_string(),
+        }
+    }
+}
+
+impl Task for DownloadFileTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run download file");
+        let dir = match &ws.web_option.download_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                ws.log("no download directory for this browser session");
+                return ExecutionResult::Failure;
+            }
+        };
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let before: HashSet<PathBuf> = task_helper::list_files(&dir);
+        let rt = Runtime::new().expect("create runtime fail");
+        if let Err(e) = rt.block_on(async {
+            let link = task_helper::find_component(driver, &self.component).await?;
+            link.click().await
+        }) {
+            ws.log(&format!("download file fail: {}", e));
+            return ExecutionResult::Failure;
+        }
+
+        let timeout = Duration::from_millis(self.timeout);
+        match task_helper::wait_for_download(&dir, &before, timeout, || ws.is_cancelled()) {
+            Some(path) => {
+                ws.log(&format!("downloaded: {}", path.display()));
+                ws.set_variable(&self.variable, &path.to_string_lossy());
+                ws.add_artifact(&path);
+                ExecutionResult::Success
+            }
+            None => {
+                ws.log("download timed out");
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct AlertTack {
+    base: OperateTask,
+    action: String,
+    input: String,
+    variable: String,
+}
+
+impl AlertTack {
+    pub fn new(action: Option<&str>, input: Option<&str>, variable: Option<&str>) -> Self {
+        AlertTack {
+            base: OperateTask::new("alert"),
+            action: action.unwrap_or("accept").to_string(),
+            input: input.unwrap_or("").to_string(),
+            variable: variable.unwrap_or("alert_text").to_string(),
+        }
+    }
+}
+
+impl Task for AlertTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run alert: {}", self.action));
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        // the text is read before acting on the alert, since it is gone afterwards
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            let text = driver.get_alert_text().await?;
+            match self.action.as_str() {
+                "text" => {}
+                "dismiss" => driver.dismiss_alert().await?,
+                "input" => {
+                    driver.send_alert_text(self.input.as_str()).await?;
+                    driver.accept_alert().await?
+                }
+                _ => driver.accept_alert().await?,
+            }
+            Ok::<String, WebDriverError>(text)
+        });
+
+        match ret {
+            Ok(text) => {
+                ws.set_variable(&self.variable, &text);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("alert fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct HttpRequestTack {
+    base: OperateTask,
+    request: HttpRequest,
+    expect: String,
+    result: String,
+}
+
+impl HttpRequestTack {
+    pub fn new(request: HttpRequest, expect: Option<&str>, result: Option<&str>) -> Self {
+        HttpRequestTack {
+            base: OperateTask::new("http_request"),
+            request,
+            expect: expect.unwrap_or("2xx").to_string(),
+            result: result.unwrap_or("response").to_string(),
+        }
+    }
+}
+
+impl Task for HttpRequestTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        let expand_pairs = |pairs: &Vec<(String, String)>| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.clone(), ws.expand(v)))
+                .collect()
+        };
+        let request = HttpRequest {
+            url: ws.expand(&self.request.url),
+            headers: expand_pairs(&self.request.headers),
+            query: expand_pairs(&self.request.query),
+            json: self.request.json.as_ref().map(|j| ws.expand(j)),
+            form: expand_pairs(&self.request.form),
+            ..self.request.clone()
+        };
+        ws.log(&format!(
+            "run http request: {} {}",
+            request.method, request.url
+        ));
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let response = match rt.block_on(misc::http_request(&request)) {
+            Ok(response) => response,
+            Err(e) => {
+                ws.log(&format!("http request fail: {}", e));
+                return ExecutionResult::Failure;
+            }
+        };
+
+        let headers = serde_json::to_string(&response.headers).unwrap_or_default();
+        ws.set_variable(
+            &format!("{}.status", self.result),
+            &response.status.to_string(),
+        );
+        ws.set_variable(&format!("{}.headers", self.result), &headers);
+        ws.set_variable(&format!("{}.body", self.result), &response.body);
+
+        if misc::status_match(&self.expect, response.status) {
+            ExecutionResult::Success
+        } else {
+            ws.log(&format!("unexpected status: {}", response.status));
+            ExecutionResult::Failure
+        }
+    }
+}
+
+pub struct ParseHtmlTack {
+    base: OperateTask,
+    source: String,
+    url: String,
+    item: Option<String>,
+    fields: Vec<FieldSpec>,
+    result: String,
+    dataset: Option<String>,
+}
+
+impl ParseHtmlTack {
+    pub fn new(
+        source: Option<&str>,
+        url: Option<&str>,
+        item: Option<&str>,
+        fields: Vec<FieldSpec>,
+        result: Option<&str>,
+        dataset: Option<&str>,
+    ) -> Self {
+        ParseHtmlTack {
+            base: OperateTask::new("parse_html"),
+            source: source.unwrap_or("response.body").to_string(),
+            url: url.unwrap_or("").to_string(),
+            item: item.map(String::from),
+            fields,
+            result: result.unwrap_or("html").to_string(),
+            dataset: dataset.map(String::from),
+        }
+    }
+}
+
+impl Task for ParseHtmlTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        // a url is fetched directly, otherwise the html comes from a variable
+        let html = if self.url.is_empty() {
+            match ws.get_variable(&self.source) {
+                Some(html) => html.clone(),
+                None => {
+                    ws.log(&format!("variable not found: {}", self.source));
+                    return ExecutionResult::Failure;
+                }
+            }
+        } else {
+            ws.log(&format!("fetch html: {}", self.url));
+            let rt = Runtime::new().expect("create runtime fail");
+            match rt.block_on(misc::http_get(&self.url)) {
+                Ok(html) => html,
+                Err(e) => {
+                    ws.log(&format!("fetch html fail: {}", e));
+                    return ExecutionResult::Failure;
+                }
+            }
+        };
+
+        ws.log("run parse html");
+        match task_helper::scrape_html(&html, self.item.as_deref(), &self.fields) {
+            Ok(rows) => match ws.store_rows(rows, &self.result, self.dataset.as_deref()) {
+                Ok(_) => ExecutionResult::Success,
+                Err(e) => {
+                    ws.log(&e);
+                    ExecutionResult::Failure
+                }
+            },
+            Err(e) => {
+                ws.log(&format!("parse html fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct ScrapeTack {
+    base: OperateTask,
+    item: Option<String>,
+    fields: Vec<FieldSpec>,
+    result: String,
+    dataset: Option<String>,
+}
+
+impl ScrapeTack {
+    pub fn new(
+        item: Option<&str>,
+        fields: Vec<FieldSpec>,
+        result: Option<&str>,
+        dataset: Option<&str>,
+    ) -> Self {
+        ScrapeTack {
+            base: OperateTask::new("scrape"),
+            item: item.map(String::from),
+            fields,
+            result: result.unwrap_or("page").to_string(),
+            dataset: dataset.map(String::from),
+        }
+    }
+}
+
+impl Task for ScrapeTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run scrape");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(task_helper::scrape_page(
+            driver,
+            self.item.as_deref(),
+            &self.fields,
+        )) {
+            Ok(rows) => match ws.store_rows(rows, &self.result, self.dataset.as_deref()) {
+                Ok(_) => ExecutionResult::Success,
+                Err(e) => {
+                    ws.log(&e);
+                    ExecutionResult::Failure
+                }
+            },
+            Err(e) => {
+                ws.log(&format!("scrape fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct JsonExtractTack {
+    base: OperateTask,
+    source: String,
+    path: String,
+    target: String,
+    all: bool,
+}
+
+impl JsonExtractTack {
+    pub fn new(source: Option<&str>, path: Option<&str>, target: Option<&str>, all: bool) -> Self {
+        JsonExtractTack {
+            base: OperateTask::new("json_extract"),
+            source: source.unwrap_or("response.body").to_string(),
+            path: path.unwrap_or("$").to_string(),
+            target: target.unwrap_or("json_value").to_string(),
+            all,
+        }
+    }
+}
+
+impl Task for JsonExtractTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run json extract: {}", self.path));
+        let raw = match ws.get_variable(&self.source) {
+            Some(raw) => raw.clone(),
+            None => {
+                ws.log(&format!("variable not found: {}", self.source));
+                return ExecutionResult::Failure;
+            }
+        };
+
+        let values = serde_json::from_str::<Value>(&raw)
+            .map_err(|e| format!("{} is not json: {}", self.source, e))
+            .and_then(|value| task_helper::json_path(&value, &self.path));
+        let values = match values {
+            Ok(values) if values.is_empty() => {
+                ws.log(&format!("no match for {} in {}", self.path, self.source));
+                return ExecutionResult::Failure;
+            }
+            Ok(values) => values,
+            Err(e) => {
+                ws.log(&e);
+                return ExecutionResult::Failure;
+            }
+        };
+
+        // a list keeps every match as a JSON array, otherwise only the first is stored
+        let text = if self.all {
+            Value::Array(values.clone()).to_string()
+        } else {
+            task_helper::value_text(&values[0])
+        };
+        ws.set_variable(&self.target, &text);
+        ws.set_variable(&format!("{}.count", self.target), &values.len().to_string());
+        ExecutionResult::Success
+    }
+}
+
+pub struct RegexExtractTack {
+    base: OperateTask,
+    source: String,
+    pattern: String,
+    target: String,
+    all: bool,
+}
+
+impl RegexExtractTack {
+    pub fn new(
+        source: Option<&str>,
+        pattern: Option<&str>,
+        target: Option<&str>,
+        all: bool,
+    ) -> Self {
+        RegexExtractTack {
+            base: OperateTask::new("regex_extract"),
+            source: source.unwrap_or("response.body").to_string(),
+            pattern: pattern.unwrap_or("").to_string(),
+            target: target.unwrap_or("regex_value").to_string(),
+            all,
+        }
+    }
+}
+
+impl Task for RegexExtractTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run regex extract: {}", self.pattern));
+        let text = match ws.get_variable(&self.source) {
+            Some(text) => text.clone(),
+            None => {
+                ws.log(&format!("variable not found: {}", self.source));
+                return ExecutionResult::Failure;
+            }
+        };
+        let re = match Regex::new(&self.pattern) {
+            Ok(re) => re,
+            Err(e) => {
+                ws.log(&format!("invalid pattern {}: {}", self.pattern, e));
+                return ExecutionResult::Failure;
+            }
+        };
+
+        let matches: Vec<regex::Captures> = re.captures_iter(&text).collect();
+        if matches.is_empty() {
+            ws.log(&format!("no match for {} in {}", self.pattern, self.source));
+            return ExecutionResult::Failure;
+        }
+
+        // the value is the first capture group, or the whole match without groups
+        let value = |cap: &regex::Captures| {
+            cap.get(1)
+                .or(cap.get(0))
+                .map_or(String::new(), |m| m.as_str().to_string())
+        };
+        if self.all {
+            let values: Vec<String> = matches.iter().map(value).collect();
+            ws.set_variable(&self.target, &json!(values).to_string());
+        } else {
+            let first = &matches[0];
+            ws.set_variable(&self.target, &value(first));
+            for (index, group) in first.iter().enumerate().skip(1) {
+                let group = group.map_or("", |m| m.as_str());
+                ws.set_variable(&format!("{}.{}", self.target, index), group);
+            }
+            for name in re.capture_names().flatten() {
+                let group = first.name(name).map_or("", |m| m.as_str());
+                ws.set_variable(&format!("{}.{}", self.target, name), group);
+            }
+        }
+        ws.set_variable(
+            &format!("{}.count", self.target),
+            &matches.len().to_string(),
+        );
+        ExecutionResult::Success
+    }
+}
+
+pub struct ScreenshotTack {
+    base: OperateTask,
+    component: String,
+    name: String,
+}
+
+impl ScreenshotTack {
+    pub fn new(comp: Option<&str>, name: Option<&str>) -> Self {
+        ScreenshotTack {
+            base: OperateTask::new("screenshot"),
+            component: comp.unwrap_or("").to_string(),
+            name: name.unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl Task for ScreenshotTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log("run screenshot");
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let name = if self.name.is_empty() {
+            format!("screenshot-{}", Utc::now().format("%H%M%S%3f"))
+        } else {
+            self.name.trim_end_matches(".png").to_string()
+        };
+        if !misc::is_safe_name(&name) {
+            ws.log(&format!("invalid screenshot name: {}", name));
+            return ExecutionResult::Failure;
+        }
+        let path = ws.artifact_dir.join(format!("{}.png", name));
+
+        // an empty component captures the whole page, otherwise only the element
+        let rt = Runtime::new().expect("create runtime fail");
+        match rt.block_on(async {
+            if self.component.is_empty() {
+                driver.screenshot(&path).await?;
+            } else {
+                let elem = task_helper::find_component(driver, &self.component).await?;
+                elem.screenshot(&path).await?;
+            }
+            Ok::<(), WebDriverError>(())
+        }) {
+            Ok(_) => {
+                ws.add_artifact(&path);
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("screenshot fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct SaveProfileTack {
+    base: OperateTask,
+    profile: String,
+}
+
+impl SaveProfileTack {
+    pub fn new(profile: Option<&str>) -> Self {
+        SaveProfileTack {
+            base: OperateTask::new("save_profile"),
+            profile: profile.unwrap_or("default").to_string(),
+        }
+    }
+}
+
+impl Task for SaveProfileTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run save profile: {}", self.profile));
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let state = match rt.block_on(profile::export_origin(driver)) {
+            Ok(state) => state,
+            Err(e) => {
+                ws.log(&format!("export session fail: {}", e));
+                return ExecutionResult::Failure;
+            }
+        };
+
+        let mut saved = Profile::load_or_default(&ws.profile_dir, &self.profile);
+        saved.merge(state);
+        match saved.save(&ws.profile_dir, &self.profile) {
+            Ok(path) => {
+                ws.log(&format!("profile saved: {}", path.display()));
+                ExecutionResult::Success
+            }
+            Err(e) => {
+                ws.log(&format!("save profile fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
+pub struct LoadProfileTack {
+    base: OperateTask,
+    profile: String,
+    url: Option<String>,
+}
+
+impl LoadProfileTack {
+    pub fn new(profile: Option<&str>, url: Option<&str>) -> Self {
+        LoadProfileTack {
+            base: OperateTask::new("load_profile"),
+            profile: profile.unwrap_or("default").to_string(),
+            url: url.map(String::from),
+        }
+    }
+}
+
+impl Task for LoadProfileTack {
+    fn execute(&self, ws: &mut Workspace) -> ExecutionResult {
+        ws.log(&format!("run load profile: {}", self.profile));
+        let saved = match Profile::load(&ws.profile_dir, &self.profile) {
+            Ok(saved) => saved,
+            Err(e) => {
+                ws.log(&format!("load profile fail: {}", e));
+                return ExecutionResult::Failure;
+            }
+        };
+        let driver = match ws.get_web_driver() {
+            Some(driver) => driver,
+            None => return ExecutionResult::Failure,
+        };
+
+        let rt = Runtime::new().expect("create runtime fail");
+        let ret = rt.block_on(async {
+            profile::import_profile(driver, &saved).await?;
+            if let Some(url) = &self.url {
+                driver.goto(url).await?;
+            }
+            Ok::<(), WebDriverError>(())
+        });
+
+        match ret {
+            Ok(_) => ExecutionResult::Success,
+            Err(e) => {
+                ws.log(&format!("restore profile fail: {}", e));
+                ExecutionResult::Failure
+            }
+        }
+    }
+}
+
 pub struct DecorateTask {
     base: BaseTask,
 }
@@ -1,7 +1,24 @@
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
 use thirtyfour::{
+    common::capabilities::firefox::FirefoxPreferences,
     error::{WebDriverError, WebDriverErrorInfo},
-    By, WebDriver, WebElement,
+    extensions::cdp::ChromeDevTools,
+    By, Capabilities, CapabilitiesHelper, ChromiumLikeCapabilities, DesiredCapabilities, Key,
+    Proxy, WebDriver, WebElement,
 };
+
+use crate::task::WebOption;
 // use tokio::runtime::Runtime;
 
 pub async fn find_component(
@@ -20,6 +37,590 @@ pub async fn find_component(
     }
 }
 
+// re-enters the frames a run went into, as `switch_frame` records them in the workspace
+pub async fn enter_frames(driver: &WebDriver, frames: &[String]) -> Result<(), WebDriverError> {
+    driver.enter_default_frame().await?;
+    for frame in frames {
+        match frame
+            .strip_prefix("index:")
+            .and_then(|i| i.parse::<u16>().ok())
+        {
+            Some(index) => driver.enter_frame(index).await?,
+            None => find_component(driver, frame).await?.enter_frame().await?,
+        }
+    }
+    Ok(())
+}
+
+fn key_parse(name: &str) -> Option<char> {
+    let key = match name.to_lowercase().as_str() {
+        "ctrl" | "control" => Key::Control,
+        "shift" => Key::Shift,
+        "alt" | "option" => Key::Alt,
+        "meta" | "cmd" | "command" | "win" => Key::Meta,
+        "tab" => Key::Tab,
+        "enter" | "return" => Key::Enter,
+        "esc" | "escape" => Key::Escape,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "insert" => Key::Insert,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => {
+            let mut chars = name.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            };
+        }
+    };
+    Some(key.into())
+}
+
+// a chord such as "ctrl+shift+t" holds every modifier while the last key is pressed
+pub async fn press_chord(driver: &WebDriver, chord: &str) -> Result<(), WebDriverError> {
+    let keys = chord
+        .split('+')
+        .map(|name| key_parse(name.trim()))
+        .collect::<Option<Vec<char>>>()
+        .ok_or(WebDriverError::ParseError(format!(
+            "invalid keys: {}",
+            chord
+        )))?;
+
+    let mut chain = driver.action_chain();
+    for key in &keys {
+        chain = chain.key_down(*key);
+    }
+    for key in keys.iter().rev() {
+        chain = chain.key_up(*key);
+    }
+    chain.perform().await
+}
+
+// `${name}` passes a workspace variable (decoded when it holds JSON), `@selector` passes
+// the located element and anything else is passed as a plain string
+pub async fn script_args(
+    driver: &WebDriver,
+    variables: &HashMap<String, String>,
+    args: &[String],
+) -> Result<Vec<Value>, WebDriverError> {
+    let mut values = Vec::new();
+    for arg in args {
+        let value = if let Some(name) = arg.strip_prefix("${").and_then(|a| a.strip_suffix('}')) {
+            let raw = variables
+                .get(name)
+                .ok_or(WebDriverError::ParseError(format!(
+                    "unknown variable: {}",
+                    name
+                )))?;
+            serde_json::from_str(raw).unwrap_or(Value::String(raw.clone()))
+        } else if let Some(selector) = arg.strip_prefix('@') {
+            find_component(driver, selector).await?.to_json()?
+        } else {
+            Value::String(arg.clone())
+        };
+        values.push(value);
+    }
+    Ok(values)
+}
+
+pub fn list_files(dir: &Path) -> HashSet<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+pub fn is_partial(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    matches!(ext, "part" | "crdownload" | "tmp" | "partial")
+}
+
+// a download is done once a new, non-temporary file keeps the same size for one poll,
+// waiting stops early when `cancelled` turns true
+pub fn wait_for_download<C: Fn() -> bool>(
+    dir: &Path,
+    before: &HashSet<PathBuf>,
+    timeout: Duration,
+    cancelled: C,
+) -> Option<PathBuf> {
+    let deadline = Instant::now() + timeout;
+    let mut last: Option<(PathBuf, u64)> = None;
+
+    while Instant::now() < deadline && !cancelled() {
+        let files = list_files(dir);
+        let pending = files.iter().any(|p| is_partial(p));
+        let found = files
+            .into_iter()
+            .filter(|p| !before.contains(p) && !is_partial(p) && p.is_file())
+            .find_map(|p| fs::metadata(&p).ok().map(|m| (p, m.len())));
+
+        match (&last, &found) {
+            (Some(prev), Some(curr)) if prev == curr && !pending => return Some(curr.0.clone()),
+            _ => last = found,
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    None
+}
+
+// chromium moves the download directory of a live session through devtools,
+// firefox only reads it when the browser starts
+// browsers resolve the download directory against their own working directory,
+// so it is created and made absolute before it is handed over
+pub fn download_dir(dir: &Path) -> PathBuf {
+    let _ = fs::create_dir_all(dir);
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+pub async fn set_download_dir(
+    driver: &WebDriver,
+    browser: &str,
+    dir: &Path,
+) -> Result<(), WebDriverError> {
+    match browser {
+        "chrome" | "edge" => {
+            let tools = ChromeDevTools::new(driver.handle.clone());
+            let params = json!({ "behavior": "allow", "downloadPath": dir.to_string_lossy() });
+            tools
+                .execute_cdp_with_params("Browser.setDownloadBehavior", params)
+                .await?;
+            Ok(())
+        }
+        other => Err(WebDriverError::ParseError(format!(
+            "{} can't change the download directory of a running session",
+            if other.is_empty() { "firefox" } else { other }
+        ))),
+    }
+}
+
+pub async fn capture_page(
+    driver: &WebDriver,
+    dir: &Path,
+    prefix: &str,
+) -> Result<Vec<PathBuf>, WebDriverError> {
+    let shot = dir.join(format!("{}.png", prefix));
+    driver.screenshot(&shot).await?;
+
+    let source = dir.join(format!("{}.html", prefix));
+    fs::write(&source, driver.source().await?)?;
+    Ok(vec![shot, source])
+}
+
+pub type Row = BTreeMap<String, String>;
+
+#[derive(Debug, Clone)]
+pub struct FieldSpec {
+    pub name: String,
+    pub selector: String,
+    pub attr: Option<String>,
+}
+
+// 'title=h2; link=a@href' reads the text of h2 and the href of a, an empty
+// selector such as 'link=@href' reads from the item element itself
+pub fn field_parse(raw: &str) -> Vec<FieldSpec> {
+    raw.split(';')
+        .filter_map(|p| p.split_once('='))
+        .map(|(name, spec)| {
+            let (selector, attr) = match spec.rsplit_once('@') {
+                Some((selector, attr)) => (selector, Some(attr.trim().to_string())),
+                None => (spec, None),
+            };
+            FieldSpec {
+                name: name.trim().to_string(),
+                selector: selector.trim().to_string(),
+                attr,
+            }
+        })
+        .collect()
+}
+
+fn css(selector: &str) -> Result<Selector, String> {
+    Selector::parse(selector).map_err(|e| format!("invalid selector {}: {}", selector, e))
+}
+
+fn html_field(elem: ElementRef, field: &FieldSpec) -> Result<String, String> {
+    let target = if field.selector.is_empty() {
+        Some(elem)
+    } else {
+        elem.select(&css(&field.selector)?).next()
+    };
+    Ok(target
+        .and_then(|t| match &field.attr {
+            Some(attr) => t.value().attr(attr).map(String::from),
+            None => Some(t.text().collect::<String>().trim().to_string()),
+        })
+        .unwrap_or_default())
+}
+
+/// Extracts one row per `item` match, or a single row from the whole document.
+pub fn scrape_html(
+    html: &str,
+    item: Option<&str>,
+    fields: &[FieldSpec],
+) -> Result<Vec<Row>, String> {
+    let document = Html::parse_document(html);
+    let items: Vec<ElementRef> = match item {
+        Some(item) => document.select(&css(item)?).collect(),
+        None => vec![document.root_element()],
+    };
+    items
+        .into_iter()
+        .map(|elem| {
+            fields
+                .iter()
+                .map(|f| Ok((f.name.clone(), html_field(elem, f)?)))
+                .collect()
+        })
+        .collect()
+}
+
+async fn page_field(elem: &WebElement, field: &FieldSpec) -> Result<String, WebDriverError> {
+    let target = if field.selector.is_empty() {
+        Some(elem.clone())
+    } else {
+        elem.find_all(By::Css(&field.selector))
+            .await?
+            .into_iter()
+            .next()
+    };
+    let target = match target {
+        Some(target) => target,
+        None => return Ok(String::new()),
+    };
+    match &field.attr {
+        Some(attr) => Ok(target.attr(attr).await?.unwrap_or_default()),
+        None => Ok(target.text().await?.trim().to_string()),
+    }
+}
+
+/// Same as `scrape_html`, reading the live page through the driver.
+pub async fn scrape_page(
+    driver: &WebDriver,
+    item: Option<&str>,
+    fields: &[FieldSpec],
+) -> Result<Vec<Row>, WebDriverError> {
+    let items = match item {
+        Some(item) => driver.find_all(By::Css(item)).await?,
+        None => vec![driver.find(By::Css("html")).await?],
+    };
+    let mut rows = Vec::new();
+    for elem in &items {
+        let mut row = Row::new();
+        for field in fields {
+            row.insert(field.name.clone(), page_field(elem, field).await?);
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Descend(Box<PathSegment>),
+}
+
+fn read_name(chars: &[char], i: &mut usize) -> Option<PathSegment> {
+    let start = *i;
+    while *i < chars.len() && chars[*i] != '.' && chars[*i] != '[' {
+        *i += 1;
+    }
+    match chars[start..*i].iter().collect::<String>().as_str() {
+        "" => None,
+        "*" => Some(PathSegment::Wildcard),
+        key => Some(PathSegment::Key(key.to_string())),
+    }
+}
+
+// supports `$.a.b`, `a[0]`, `[-1]`, `['key']`, `*`, `[*]` and `..key`
+fn path_parse(path: &str) -> Result<Vec<PathSegment>, String> {
+    let invalid = || format!("invalid json path: {}", path);
+    let chars: Vec<char> = path.trim().trim_start_matches('$').chars().collect();
+    let mut segments = Vec::new();
+    let mut descend = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let segment = match chars[i] {
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .ok_or_else(invalid)?
+                    + i;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                i = end + 1;
+                if inner == "*" {
+                    PathSegment::Wildcard
+                } else if let Ok(index) = inner.parse::<i64>() {
+                    PathSegment::Index(index)
+                } else {
+                    let key = inner.trim_matches(|c| c == '\'' || c == '"');
+                    if key.is_empty() {
+                        return Err(invalid());
+                    }
+                    PathSegment::Key(key.to_string())
+                }
+            }
+            '.' => {
+                if chars.get(i + 1) == Some(&'.') {
+                    descend = true;
+                    i += 1;
+                    if chars.get(i + 1) == Some(&'[') {
+                        i += 1;
+                        continue;
+                    }
+                }
+                i += 1;
+                read_name(&chars, &mut i).ok_or_else(invalid)?
+            }
+            _ if i == 0 => read_name(&chars, &mut i).ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        segments.push(match descend {
+            true => PathSegment::Descend(Box::new(segment)),
+            false => segment,
+        });
+        descend = false;
+    }
+
+    if descend {
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+fn path_step(value: &Value, segment: &PathSegment, out: &mut Vec<Value>) {
+    match (segment, value) {
+        (PathSegment::Key(key), Value::Object(map)) => out.extend(map.get(key).cloned()),
+        (PathSegment::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            out.extend(
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|i| items.get(i))
+                    .cloned(),
+            );
+        }
+        (PathSegment::Wildcard, Value::Array(items)) => out.extend(items.iter().cloned()),
+        (PathSegment::Wildcard, Value::Object(map)) => out.extend(map.values().cloned()),
+        (PathSegment::Descend(inner), _) => {
+            path_step(value, inner, out);
+            let children: Vec<&Value> = match value {
+                Value::Array(items) => items.iter().collect(),
+                Value::Object(map) => map.values().collect(),
+                _ => Vec::new(),
+            };
+            for child in children {
+                path_step(child, segment, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn json_path(value: &Value, path: &str) -> Result<Vec<Value>, String> {
+    let mut current = vec![value.clone()];
+    for segment in path_parse(path)? {
+        let mut next = Vec::new();
+        for value in &current {
+            path_step(value, &segment, &mut next);
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+// strings are kept as they are, anything else is stored as JSON text
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn cron_parse(expr: &str) -> Result<Schedule, String> {
+    Schedule::from_str(expr).map_err(|e| format!("invalid cron expression {}: {}", expr, e))
+}
+
+// an empty name or `local` means the system timezone
+pub fn tz_parse(name: &str) -> Result<Option<Tz>, String> {
+    match name.trim() {
+        "" | "local" => Ok(None),
+        name => Tz::from_str(name)
+            .map(Some)
+            .map_err(|_| format!("invalid timezone: {}", name)),
+    }
+}
+
+// the schedule is evaluated as wall clock time in the zone, so a fire time
+// falling into a DST gap is skipped and a repeated one fires once, at the earlier instant
+pub fn next_fire(
+    schedule: &Schedule,
+    tz: Option<Tz>,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match tz {
+        Some(tz) => schedule
+            .after(&after.with_timezone(&tz))
+            .next()
+            .map(|t| t.with_timezone(&Utc)),
+        None => schedule
+            .after(&after.with_timezone(&Local))
+            .next()
+            .map(|t| t.with_timezone(&Utc)),
+    }
+}
+
+fn pref_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+fn manual_proxy(addr: &str) -> Proxy {
+    Proxy::Manual {
+        ftp_proxy: None,
+        http_proxy: Some(addr.to_string()),
+        ssl_proxy: Some(addr.to_string()),
+        socks_proxy: None,
+        socks_version: None,
+        socks_username: None,
+        socks_password: None,
+        no_proxy: None,
+    }
+}
+
+fn firefox_capabilities(option: &WebOption) -> Result<Capabilities, Box<dyn Error>> {
+    let mut caps = DesiredCapabilities::firefox();
+    let mut prefs = FirefoxPreferences::new();
+
+    if option.headless {
+        caps.set_headless()?;
+    }
+    if let Some((width, height)) = option.window_size {
+        caps.add_arg(&format!("--width={}", width))?;
+        caps.add_arg(&format!("--height={}", height))?;
+    }
+    if let Some(agent) = &option.user_agent {
+        prefs.set_user_agent(agent.clone())?;
+    }
+    if let Some(proxy) = &option.proxy {
+        caps.set_proxy(manual_proxy(proxy))?;
+    }
+    if option.insecure {
+        caps.accept_insecure_certs(true)?;
+    }
+    if let Some(dir) = &option.download_dir {
+        prefs.set("browser.download.folderList", 2)?;
+        prefs.set("browser.download.dir", dir)?;
+        prefs.set("browser.download.useDownloadDir", true)?;
+        prefs.set(
+            "browser.download.always_ask_before_handling_new_types",
+            false,
+        )?;
+    }
+    for arg in &option.args {
+        caps.add_arg(arg)?;
+    }
+    for (key, value) in &option.prefs {
+        prefs.set(key, pref_value(value))?;
+    }
+
+    caps.set_preferences(prefs)?;
+    Ok(caps.into())
+}
+
+fn chromium_capabilities<C>(mut caps: C, option: &WebOption) -> Result<Capabilities, Box<dyn Error>>
+where
+    C: ChromiumLikeCapabilities + Into<Capabilities>,
+{
+    let mut prefs = Map::new();
+
+    if option.headless {
+        caps.set_headless()?;
+    }
+    if let Some((width, height)) = option.window_size {
+        caps.add_arg(&format!("--window-size={},{}", width, height))?;
+    }
+    if let Some(agent) = &option.user_agent {
+        caps.add_arg(&format!("--user-agent={}", agent))?;
+    }
+    if let Some(proxy) = &option.proxy {
+        caps.set_proxy(manual_proxy(proxy))?;
+    }
+    if option.insecure {
+        caps.accept_insecure_certs(true)?;
+    }
+    if let Some(dir) = &option.download_dir {
+        prefs.insert("download.default_directory".to_string(), json!(dir));
+        prefs.insert("download.prompt_for_download".to_string(), json!(false));
+    }
+    for arg in &option.args {
+        caps.add_arg(arg)?;
+    }
+    for (key, value) in &option.prefs {
+        prefs.insert(key.to_string(), pref_value(value));
+    }
+
+    if !prefs.is_empty() {
+        caps.add_experimental_option("prefs", prefs)?;
+    }
+    Ok(caps.into())
+}
+
+pub fn build_capabilities(option: &WebOption) -> Result<Capabilities, Box<dyn Error>> {
+    let mut caps = match option.browser.as_str() {
+        "" | "firefox" => firefox_capabilities(option)?,
+        "chrome" => chromium_capabilities(DesiredCapabilities::chrome(), option)?,
+        "edge" => chromium_capabilities(DesiredCapabilities::edge(), option)?,
+        other => return Err(format!("unsupported browser: {}", other).into()),
+    };
+
+    // W3C values: accept, dismiss, accept and notify, dismiss and notify, ignore
+    if let Some(policy) = &option.unhandled_alert {
+        let policy = policy.replace('_', " ");
+        let valid = [
+            "accept",
+            "dismiss",
+            "accept and notify",
+            "dismiss and notify",
+            "ignore",
+        ];
+        if !valid.contains(&policy.as_str()) {
+            let message = format!("unsupported alert policy: {}", policy);
+            return Err(message.into());
+        }
+        caps.insert("unhandledPromptBehavior".to_string(), json!(policy));
+    }
+    Ok(caps)
+}
+
 // pub fn quit_driver(driver: WebDriver) {
 //     let rt = Runtime::new().expect("Failed to create runtime");
 //     let _ = rt.block_on(async {
@@ -34,3 +635,146 @@ pub async fn find_component(
 //         .await?;
 //     Ok(elem)
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PathSegment::*;
+
+    fn key(name: &str) -> PathSegment {
+        Key(name.to_string())
+    }
+
+    fn sample() -> Value {
+        json!({
+            "store": {
+                "name": "mashu",
+                "books": [
+                    { "title": "a", "price": 8, "tags": ["x"] },
+                    { "title": "b", "price": 12 },
+                    { "title": "c", "price": 5, "author": { "name": "z" } }
+                ]
+            },
+            "odd key": true
+        })
+    }
+
+    fn texts(path: &str) -> Vec<String> {
+        json_path(&sample(), path)
+            .unwrap()
+            .iter()
+            .map(value_text)
+            .collect()
+    }
+
+    #[test]
+    fn path_parse_reads_every_segment_form() {
+        assert_eq!(path_parse("$.a.b").unwrap(), vec![key("a"), key("b")]);
+        assert_eq!(path_parse("a[0]").unwrap(), vec![key("a"), Index(0)]);
+        assert_eq!(path_parse("$[-1]").unwrap(), vec![Index(-1)]);
+        assert_eq!(path_parse("['odd key']").unwrap(), vec![key("odd key")]);
+        assert_eq!(path_parse("a.*").unwrap(), vec![key("a"), Wildcard]);
+        assert_eq!(path_parse("a[*]").unwrap(), vec![key("a"), Wildcard]);
+        assert_eq!(
+            path_parse("$..name").unwrap(),
+            vec![Descend(Box::new(key("name")))]
+        );
+        assert_eq!(
+            path_parse("a..[0]").unwrap(),
+            vec![key("a"), Descend(Box::new(Index(0)))]
+        );
+        assert_eq!(path_parse("$").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn path_parse_rejects_broken_paths() {
+        for path in ["a[0", "a.", "a..", "a[]", "a['']", "a[0]b"] {
+            assert!(path_parse(path).is_err(), "{} should be refused", path);
+        }
+    }
+
+    #[test]
+    fn json_path_selects_keys_and_indices() {
+        assert_eq!(texts("$.store.name"), vec!["mashu"]);
+        assert_eq!(texts("store.books[1].title"), vec!["b"]);
+        assert_eq!(texts("store.books[-1].title"), vec!["c"]);
+        assert_eq!(texts("['odd key']"), vec!["true"]);
+        assert_eq!(texts("store.books[0].tags"), vec![r#"["x"]"#]);
+    }
+
+    #[test]
+    fn json_path_expands_wildcards_and_descent() {
+        assert_eq!(texts("store.books[*].price"), vec!["8", "12", "5"]);
+        assert_eq!(texts("store.books.*.title"), vec!["a", "b", "c"]);
+        assert_eq!(texts("$..name"), vec!["mashu", "z"]);
+        assert_eq!(texts("store..[0]").len(), 2);
+    }
+
+    #[test]
+    fn json_path_misses_are_empty() {
+        assert!(texts("store.missing").is_empty());
+        assert!(texts("store.books[3]").is_empty());
+        assert!(texts("store.books[-4]").is_empty());
+        assert!(texts("store.name[0]").is_empty());
+    }
+
+    fn at(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn tz_parse_reads_names_and_local() {
+        assert_eq!(
+            tz_parse("America/New_York").unwrap(),
+            Some(chrono_tz::America::New_York)
+        );
+        assert_eq!(tz_parse("").unwrap(), None);
+        assert_eq!(tz_parse(" local ").unwrap(), None);
+        assert!(tz_parse("Mars/Olympus").is_err());
+        assert!(cron_parse("0 61 * * * * *").is_err());
+    }
+
+    #[test]
+    fn next_fire_skips_the_spring_forward_gap() {
+        // 02:30 does not exist in New York on 2026-03-08
+        let schedule = cron_parse("0 30 2 * * * *").unwrap();
+        let tz = tz_parse("America/New_York").unwrap();
+        let next = next_fire(&schedule, tz, at("2026-03-08T00:00:00-05:00")).unwrap();
+        assert_eq!(next, at("2026-03-09T02:30:00-04:00"));
+    }
+
+    #[test]
+    fn next_fire_runs_a_repeated_time_once() {
+        // 01:30 happens twice in New York on 2026-11-01, first in EDT then in EST
+        let schedule = cron_parse("0 30 1 * * * *").unwrap();
+        let tz = tz_parse("America/New_York").unwrap();
+        let first = next_fire(&schedule, tz, at("2026-11-01T00:00:00-04:00")).unwrap();
+        assert_eq!(first, at("2026-11-01T01:30:00-04:00"));
+        let second = next_fire(&schedule, tz, first).unwrap();
+        assert_eq!(second, at("2026-11-02T01:30:00-05:00"));
+    }
+
+    #[test]
+    fn next_fire_falls_back_to_the_local_zone() {
+        let schedule = cron_parse("0 0 * * * * *").unwrap();
+        let after = at("2026-06-01T12:10:00Z");
+        let next = next_fire(&schedule, None, after).unwrap();
+        assert!(next > after);
+        assert_eq!(
+            next.with_timezone(&Local).format("%M:%S").to_string(),
+            "00:00"
+        );
+    }
+
+    #[test]
+    fn download_dir_is_created_and_absolute() {
+        let dir = Path::new("target").join("mashu-test-downloads");
+        let _ = fs::remove_dir_all(&dir);
+        let made = download_dir(&dir);
+        assert!(made.is_absolute());
+        assert!(made.is_dir());
+        assert!(made.ends_with("mashu-test-downloads"));
+    }
+}
@@ -0,0 +1,136 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use crate::task_helper;
+
+const MAX_BODY: usize = 1024 * 1024;
+
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            if key.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(format!("body exceeds {} bytes", MAX_BODY));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    Ok(Request {
+        method,
+        path,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = serde_json::json!({ "message": message }).to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+// a JSON object body becomes one input per key, the raw body is always kept as `body`
+fn body_inputs(body: &str) -> Result<HashMap<String, String>, String> {
+    let mut inputs = HashMap::new();
+    if !body.trim().is_empty() {
+        match serde_json::from_str::<Value>(body) {
+            Ok(Value::Object(map)) => {
+                for (key, value) in &map {
+                    inputs.insert(key.clone(), task_helper::value_text(value));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => return Err(format!("invalid json body: {}", e)),
+        }
+    }
+    inputs.insert("body".to_string(), body.to_string());
+    Ok(inputs)
+}
+
+fn handle_connection<H>(stream: &mut TcpStream, path: &str, handle: &H)
+where
+    H: Fn(HashMap<String, String>) -> Result<String, String>,
+{
+    let request = match read_request(stream) {
+        Ok(request) => request,
+        Err(e) => return respond(stream, "400 Bad Request", &e),
+    };
+    if request.method != "POST" {
+        return respond(stream, "405 Method Not Allowed", "only POST is accepted");
+    }
+    if request.path.split('?').next() != Some(path) {
+        return respond(stream, "404 Not Found", "unknown path");
+    }
+    let inputs = match body_inputs(&request.body) {
+        Ok(inputs) => inputs,
+        Err(e) => return respond(stream, "400 Bad Request", &e),
+    };
+    match handle(inputs) {
+        Ok(message) => respond(stream, "202 Accepted", &message),
+        Err(e) => respond(stream, "409 Conflict", &e),
+    }
+}
+
+/// Listens on a local port and hands every POST to `path` to `handle`,
+/// the listener stops once `alive` turns false.
+pub fn serve<A, H>(port: u16, path: &str, alive: A, handle: H) -> Result<(), String>
+where
+    A: Fn() -> bool + Send + 'static,
+    H: Fn(HashMap<String, String>) -> Result<String, String> + Send + 'static,
+{
+    let listener =
+        TcpListener::bind(("127.0.0.1", port)).map_err(|e| format!("bind {} fail: {}", port, e))?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+    let path = path.to_string();
+    thread::spawn(move || {
+        while alive() {
+            match listener.accept() {
+                Ok((mut stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+                    handle_connection(&mut stream, &path, &handle);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(200));
+                }
+                Err(e) => {
+                    println!("webhook {} accept fail: {}", port, e);
+                    thread::sleep(Duration::from_millis(200));
+                }
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,372 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    pub node_type: String,
+    #[serde(default)]
+    pub para: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub source: String,
+    pub label: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Flow {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    NodeAdded {
+        node_id: String,
+        name: String,
+        node_type: String,
+    },
+    NodeRemoved {
+        node_id: String,
+        name: String,
+        node_type: String,
+    },
+    NodeChanged {
+        node_id: String,
+        field: String,
+        from: String,
+        to: String,
+    },
+    ParamAdded {
+        node_id: String,
+        key: String,
+        value: String,
+    },
+    ParamRemoved {
+        node_id: String,
+        key: String,
+        value: String,
+    },
+    ParamChanged {
+        node_id: String,
+        key: String,
+        from: String,
+        to: String,
+    },
+    EdgeAdded {
+        source: String,
+        label: String,
+        target: String,
+    },
+    EdgeRemoved {
+        source: String,
+        label: String,
+        target: String,
+    },
+}
+
+fn params_parse(para: &str) -> BTreeMap<String, String> {
+    let re = Regex::new(r"(\w+):\s*'([^']*)'").expect("invalid param pattern");
+    re.captures_iter(para)
+        .map(|cap| {
+            let value = cap[2].replace("#quot;", "\"").replace("#39;", "'");
+            (cap[1].to_string(), value)
+        })
+        .collect()
+}
+
+/// Reads the mermaid-like source into nodes and edges, in source order.
+pub fn parse(source: &str) -> Result<Flow, Box<dyn Error>> {
+    let node_pattern = Regex::new(
+        r#"(\w+)\["name:\s*([\w\s]+),\s*type:\s*(\w+)(?:,\s*para:\s*(\{[^"]*\}))?\s*"\]"#,
+    )?;
+    let edge_pattern = Regex::new(r#"(\w+)\s*-->\|\s*(\w+)\s*\|\s*(\w+)"#)?;
+
+    let nodes = node_pattern
+        .captures_iter(source)
+        .map(|cap| {
+            let para = cap.get(4).map_or("", |p| p.as_str()).to_string();
+            Node {
+                id: cap.get(1).map_or("", |p| p.as_str()).to_string(),
+                name: cap.get(2).map_or("", |p| p.as_str()).to_string(),
+                node_type: cap.get(3).map_or("", |p| p.as_str()).to_string(),
+                params: params_parse(&para),
+                para,
+            }
+        })
+        .collect();
+    let edges = edge_pattern
+        .captures_iter(source)
+        .map(|cap| Edge {
+            source: cap.get(1).map_or("", |p| p.as_str()).to_string(),
+            label: cap.get(2).map_or("", |p| p.as_str()).to_string(),
+            target: cap.get(3).map_or("", |p| p.as_str()).to_string(),
+        })
+        .collect();
+
+    Ok(Flow { nodes, edges })
+}
+
+/// Writes a flow back as source `parse` accepts. `params` are used when `para`
+/// is empty, so a hand written JSON flow only needs one of them.
+pub fn render(flow: &Flow) -> String {
+    let mut lines = vec!["flowchart TD".to_string()];
+    for node in &flow.nodes {
+        let para = match node.para.trim().is_empty() {
+            false => node.para.clone(),
+            true if node.params.is_empty() => String::new(),
+            true => {
+                let pairs: Vec<String> = node
+                    .params
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = value.replace('"', "#quot;").replace('\'', "#39;");
+                        format!("{}: '{}'", key, value)
+                    })
+                    .collect();
+                format!("{{{}}}", pairs.join(", "))
+            }
+        };
+        match para.is_empty() {
+            true => lines.push(format!(
+                "    {}[\"name: {}, type: {}\"]",
+                node.id, node.name, node.node_type
+            )),
+            false => lines.push(format!(
+                "    {}[\"name: {}, type: {}, para: {}\"]",
+                node.id, node.name, node.node_type, para
+            )),
+        }
+    }
+    for edge in &flow.edges {
+        lines.push(format!(
+            "    {} -->|{}| {}",
+            edge.source, edge.label, edge.target
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn content_hash(source: &str) -> String {
+    format!("{:x}", Sha256::digest(source.as_bytes()))
+}
+
+fn param_changes(node_id: &str, old: &Node, new: &Node) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (key, value) in &old.params {
+        match new.params.get(key) {
+            None => changes.push(Change::ParamRemoved {
+                node_id: node_id.to_string(),
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            Some(to) if to != value => changes.push(Change::ParamChanged {
+                node_id: node_id.to_string(),
+                key: key.clone(),
+                from: value.clone(),
+                to: to.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in &new.params {
+        if !old.params.contains_key(key) {
+            changes.push(Change::ParamAdded {
+                node_id: node_id.to_string(),
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    changes
+}
+
+/// Compares two flows node by node (matched on id), then parameter by parameter
+/// and edge by edge, so layout and formatting changes never show up.
+pub fn diff(old: &Flow, new: &Flow) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for node in &old.nodes {
+        let other = match new.nodes.iter().find(|n| n.id == node.id) {
+            Some(other) => other,
+            None => {
+                changes.push(Change::NodeRemoved {
+                    node_id: node.id.clone(),
+                    name: node.name.clone(),
+                    node_type: node.node_type.clone(),
+                });
+                continue;
+            }
+        };
+        let fields = [
+            ("name", &node.name, &other.name),
+            ("type", &node.node_type, &other.node_type),
+        ];
+        for (field, from, to) in fields {
+            if from.trim() != to.trim() {
+                changes.push(Change::NodeChanged {
+                    node_id: node.id.clone(),
+                    field: field.to_string(),
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+        changes.extend(param_changes(&node.id, node, other));
+    }
+    for node in &new.nodes {
+        if !old.nodes.iter().any(|n| n.id == node.id) {
+            changes.push(Change::NodeAdded {
+                node_id: node.id.clone(),
+                name: node.name.clone(),
+                node_type: node.node_type.clone(),
+            });
+        }
+    }
+
+    for edge in old.edges.iter().filter(|e| !new.edges.contains(e)) {
+        changes.push(Change::EdgeRemoved {
+            source: edge.source.clone(),
+            label: edge.label.clone(),
+            target: edge.target.clone(),
+        });
+    }
+    for edge in new.edges.iter().filter(|e| !old.edges.contains(e)) {
+        changes.push(Change::EdgeAdded {
+            source: edge.source.clone(),
+            label: edge.label.clone(),
+            target: edge.target.clone(),
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+        flowchart TD
+        a["name: head, type: control"]
+        b["name: open_web, type: operate, para: {url: 'https://a.test'}"]
+        c["name: end, type: control"]
+        a -->|success| b
+        b -->|always| c
+    "#;
+
+    fn diff_sources(old: &str, new: &str) -> Vec<Change> {
+        diff(&parse(old).unwrap(), &parse(new).unwrap())
+    }
+
+    #[test]
+    fn parse_reads_nodes_params_and_edges() {
+        let flow = parse(BASE).unwrap();
+        assert_eq!(flow.nodes.len(), 3);
+        assert_eq!(flow.nodes[1].node_type, "operate");
+        assert_eq!(flow.nodes[1].params["url"], "https://a.test");
+        assert_eq!(
+            flow.edges[1],
+            Edge {
+                source: "b".to_string(),
+                label: "always".to_string(),
+                target: "c".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn diff_ignores_layout() {
+        let moved = BASE.replace("        ", "  ").replace("-->|", "-->| ");
+        assert!(diff_sources(BASE, &moved).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_params() {
+        let new = BASE.replace("url: 'https://a.test'", "url: 'https://b.test', wait: '1'");
+        assert_eq!(
+            diff_sources(BASE, &new),
+            vec![
+                Change::ParamChanged {
+                    node_id: "b".to_string(),
+                    key: "url".to_string(),
+                    from: "https://a.test".to_string(),
+                    to: "https://b.test".to_string(),
+                },
+                Change::ParamAdded {
+                    node_id: "b".to_string(),
+                    key: "wait".to_string(),
+                    value: "1".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            diff_sources(&new, BASE)[1],
+            Change::ParamRemoved {
+                node_id: "b".to_string(),
+                key: "wait".to_string(),
+                value: "1".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn diff_reports_nodes_and_edges() {
+        let new = BASE
+            .replace("type: operate", "type: decorate")
+            .replace(
+                "b -->|always| c",
+                "b -->|success| d\n d[\"name: end, type: control\"]",
+            )
+            .replace("c[\"name: end, type: control\"]", "");
+        let changes = diff_sources(BASE, &new);
+        assert_eq!(
+            changes,
+            vec![
+                Change::NodeChanged {
+                    node_id: "b".to_string(),
+                    field: "type".to_string(),
+                    from: "operate".to_string(),
+                    to: "decorate".to_string(),
+                },
+                Change::NodeRemoved {
+                    node_id: "c".to_string(),
+                    name: "end".to_string(),
+                    node_type: "control".to_string(),
+                },
+                Change::NodeAdded {
+                    node_id: "d".to_string(),
+                    name: "end".to_string(),
+                    node_type: "control".to_string(),
+                },
+                Change::EdgeRemoved {
+                    source: "b".to_string(),
+                    label: "always".to_string(),
+                    target: "c".to_string(),
+                },
+                Change::EdgeAdded {
+                    source: "b".to_string(),
+                    label: "success".to_string(),
+                    target: "d".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn render_round_trips() {
+        let flow = parse(BASE).unwrap();
+        let again = parse(&render(&flow)).unwrap();
+        assert_eq!(flow.nodes, again.nodes);
+        assert_eq!(flow.edges, again.edges);
+    }
+}